@@ -0,0 +1,112 @@
+//! Converting snippets to and from other formats. This requires the `std` feature, and the
+//! feature of the format.
+
+#[cfg(feature = "csv")]
+use std::io;
+
+use crate::SnippetParser;
+#[cfg(any(feature = "csv", feature = "toml"))]
+use crate::{Snippet, SnippetError};
+
+impl<'a> SnippetParser<'a> {
+    /// Returns all snippets as a TOML document, with an array of tables named `snippets` that
+    /// each have a `title` and a `body`. Bodies with multiple lines are written as multi-line
+    /// strings. This requires the `toml` feature.
+    ///
+    /// ```toml
+    /// [[snippets]]
+    /// body = '''
+    /// Are we human?
+    /// Or are we dancer?'''
+    /// title = "snippet1"
+    /// ```
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, like [`get_snippets`](SnippetParser::get_snippets).
+    #[cfg(feature = "toml")]
+    pub fn to_toml(&self) -> Result<String, SnippetError> {
+        let snippets = self.get_snippets()?
+            .into_iter()
+            .map(|snip| {
+                let mut table = toml::Table::new();
+                table.insert("title".to_string(), toml::Value::String(snip.title));
+                table.insert("body".to_string(), toml::Value::String(snip.s));
+                toml::Value::Table(table)
+            })
+            .collect();
+        let mut document = toml::Table::new();
+        document.insert("snippets".to_string(), toml::Value::Array(snippets));
+        toml::to_string(&document).map_err(|err| SnippetError::Toml(err.to_string()))
+    }
+    
+    /// Creates a new struct containing the snippets of a TOML document in the format written by
+    /// [`to_toml`](SnippetParser::to_toml). This requires the `toml` feature.
+    ///
+    /// # Errors
+    /// Returns [`SnippetError::Toml`] if `contents` is not valid TOML, or if a snippet is not a
+    /// table with a string `title` and `body`. A document without `snippets` contains no snippets.
+    #[cfg(feature = "toml")]
+    pub fn from_toml(contents: &str) -> Result<Self, SnippetError> {
+        let document: toml::Table = contents.parse().map_err(|err: toml::de::Error| SnippetError::Toml(err.message().to_string()))?;
+        let tables = match document.get("snippets") {
+            Some(toml::Value::Array(tables)) => tables.as_slice(),
+            Some(_) => return Err(SnippetError::Toml("`snippets` is not an array".to_string())),
+            None => &[]
+        };
+        let mut snippets = Vec::with_capacity(tables.len());
+        for (index, table) in tables.iter().enumerate() {
+            let field = |name: &str| {
+                table.get(name)
+                    .and_then(toml::Value::as_str)
+                    .map(str::to_string)
+                    .ok_or_else(|| SnippetError::Toml(format!("snippet {} has no string `{}`", index, name)))
+            };
+            snippets.push(Snippet::new(field("title")?, field("body")?));
+        }
+        Ok(Self::from_snippets(snippets))
+    }
+    
+    /// Writes all snippets to `w` as CSV, with a header row `title,body` and a row for every
+    /// snippet. Fields are quoted when needed, so bodies can contain commas, quotes and multiple
+    /// lines. This requires the `csv` feature.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, like
+    /// [`get_snippets`](SnippetParser::get_snippets), or if `w` could not be written to.
+    #[cfg(feature = "csv")]
+    pub fn to_csv<W: io::Write>(&self, w: &mut W) -> Result<(), SnippetError> {
+        let snippets = self.get_snippets()?;
+        let mut writer = csv::Writer::from_writer(w);
+        writer.write_record(["title", "body"]).map_err(|err| SnippetError::Csv(err.to_string()))?;
+        for snip in &snippets {
+            writer.write_record([&snip.title, &snip.s]).map_err(|err| SnippetError::Csv(err.to_string()))?;
+        }
+        Ok(writer.flush()?)
+    }
+    
+    /// Creates a new struct containing the snippets of a CSV document in the format written by
+    /// [`to_csv`](SnippetParser::to_csv). The `title` and `body` columns are found by the header
+    /// row, and other columns are ignored. This requires the `csv` feature.
+    ///
+    /// # Errors
+    /// Returns [`SnippetError::Csv`] if `r` could not be read or is not valid CSV, or if the header
+    /// row has no `title` or `body` column.
+    #[cfg(feature = "csv")]
+    pub fn from_csv<R: io::Read>(r: R) -> Result<Self, SnippetError> {
+        let mut reader = csv::Reader::from_reader(r);
+        let headers = reader.headers().map_err(|err| SnippetError::Csv(err.to_string()))?;
+        let column = |name: &str| {
+            headers.iter()
+                .position(|header| header == name)
+                .ok_or_else(|| SnippetError::Csv(format!("no `{}` column", name)))
+        };
+        let (title, body) = (column("title")?, column("body")?);
+        let mut snippets = Vec::new();
+        for record in reader.records() {
+            let record = record.map_err(|err| SnippetError::Csv(err.to_string()))?;
+            let field = |index: usize| record.get(index).unwrap_or_default().to_string();
+            snippets.push(Snippet::new(field(title), field(body)));
+        }
+        Ok(Self::from_snippets(snippets))
+    }
+}
@@ -0,0 +1,466 @@
+//! Parsing of the snippet format. This only depends on `alloc`, so it can be used without `std`.
+
+use alloc::collections::BTreeSet;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+
+use crate::{Snippet, SnippetError};
+
+/// Options that change how snippets are parsed.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub(crate) struct ParseOptions {
+    pub(crate) trim_unicode_titles: bool,
+    pub(crate) preserve_headers: bool,
+    pub(crate) strict: bool,
+    pub(crate) lenient_end_markers: bool,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) max_line_length: Option<usize>,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) lossy_utf8: bool,
+    pub(crate) verify_checksums: bool,
+    pub(crate) trim_body_trailing_newline: bool,
+    pub(crate) auto_dedent: bool,
+    #[cfg_attr(not(feature = "std"), allow(dead_code))]
+    pub(crate) write_checksums: bool,
+    pub(crate) termination_mode: TerminationMode,
+    /// What a header starts with instead of `--`
+    pub(crate) title_prefix: Option<String>,
+    /// What a header ends with instead of `--`
+    pub(crate) title_suffix: Option<String>
+}
+
+/// Determines what ends a snippet, besides an end marker (`-- end --`).
+///
+/// In the modes other than [`EndMarker`](TerminationMode::EndMarker), a line is only a header if
+/// it starts and ends with the delimiters, like `-- title --`, so that body lines containing `--`
+/// don't end a snippet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TerminationMode {
+    /// Only an end marker ends a snippet. A last snippet without an end marker is ignored.
+    #[default]
+    EndMarker,
+    /// A blank line or the end of the file also ends a snippet, so bodies can't contain blank
+    /// lines.
+    BlankLine,
+    /// The header of the next snippet or the end of the file also ends a snippet.
+    NextHeader
+}
+
+/// State kept between reading snippets from the same file.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ParseState {
+    /// The number of the last line that was read, starting at 1
+    pub(crate) line_number: usize,
+    /// Non-blank lines outside of snippets that are not comments, with their line numbers. Only
+    /// collected in strict mode.
+    pub(crate) stray_lines: Vec<(usize, String)>,
+    /// A header that ended the previous snippet in [`TerminationMode::NextHeader`], which starts
+    /// the next snippet.
+    pub(crate) pending_header: Option<String>,
+    /// Problems found while reading, which did not prevent reading the snippets
+    pub(crate) warnings: Vec<ParseWarning>,
+    /// The titles of snippets whose checksum did not match their string. Only collected when
+    /// checksums are verified.
+    pub(crate) checksum_mismatches: Vec<String>,
+    /// Comment lines outside of snippets, which are only collected when this is not `None`
+    pub(crate) comments: Option<Vec<String>>,
+    /// Whether the lines being read are part of the front matter at the top of the file
+    in_front_matter: bool,
+    /// Whether a `---` line at the top of the file is read as ordinary content instead of
+    /// starting front matter
+    ignore_front_matter: bool
+}
+
+/// The header of a snippet that is being read.
+struct Header {
+    title: String,
+    /// The header line, when headers are preserved
+    line: Option<String>,
+    /// The CRC-32 checksum of the string, if the header contains one like `{crc32:abcd1234}`
+    checksum: Option<u32>
+}
+
+/// A problem in a snippet file that does not prevent reading its snippets.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// The header at `line` has an empty title.
+    EmptyTitle { line: usize }
+}
+
+impl Display for ParseWarning {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            ParseWarning::EmptyTitle { line } => write!(f, "the header at line {} has an empty title", line)
+        }
+    }
+}
+
+/// A problem in a snippet file found by [`SnippetParser::lint`](crate::SnippetParser::lint).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LintIssue {
+    /// The end marker at `line` does not end a snippet.
+    OrphanEndMarker { line: usize },
+    /// The snippet with the header at `line` has no lines before its end marker.
+    EmptySnippet { line: usize, title: String },
+    /// The header at `line` has the same title as an earlier snippet.
+    DuplicateTitle { line: usize, title: String },
+    /// The front matter at the top of the file is never closed by a `---` line.
+    UnterminatedFrontMatter
+}
+
+impl Display for LintIssue {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            LintIssue::OrphanEndMarker { line } => write!(f, "line {}: end marker outside of a snippet", line),
+            LintIssue::EmptySnippet { line, title } => write!(f, "line {}: snippet \"{}\" has no body", line, title),
+            LintIssue::DuplicateTitle { line, title } => write!(f, "line {}: duplicate title \"{}\"", line, title),
+            LintIssue::UnterminatedFrontMatter => write!(f, "line 1: front matter is never closed")
+        }
+    }
+}
+
+/// Characters that are not considered whitespace by [`str::trim`], but are invisible and commonly
+/// end up around titles when copy-pasting.
+const INVISIBLE_TITLE_CHARS: [char; 5] = ['\u{200B}', '\u{200C}', '\u{200D}', '\u{2060}', '\u{FEFF}'];
+
+impl ParseOptions {
+    /// Trims a title read from a snippet header.
+    fn trim_title(&self, title: &str) -> String {
+        if self.trim_unicode_titles {
+            title.trim_matches(|c: char| c.is_whitespace() || INVISIBLE_TITLE_CHARS.contains(&c)).to_string()
+        } else {
+            title.trim().to_string()
+        }
+    }
+    
+    /// Returns whether the prefix or suffix of headers was changed from `--`.
+    fn has_custom_delimiters(&self) -> bool {
+        self.title_prefix.is_some() || self.title_suffix.is_some()
+    }
+    
+    /// Returns the title in the header `line` with the prefix and suffix removed, or `None` if
+    /// `line` does not start with the prefix and end with the suffix.
+    fn strip_header_delimiters<'l>(&self, line: &'l str) -> Option<&'l str> {
+        let prefix = self.title_prefix.as_deref().unwrap_or("--");
+        let suffix = self.title_suffix.as_deref().unwrap_or("--");
+        let line = line.trim();
+        if line.len() < prefix.len() + suffix.len() {
+            return None;
+        }
+        line.strip_prefix(prefix)?.strip_suffix(suffix)
+    }
+    
+    /// Returns whether `line` is the header of a snippet. An end marker is never a header.
+    ///
+    /// With the default delimiters and [`TerminationMode::EndMarker`], any line containing `--`
+    /// is a header. Otherwise, a header has to start with the prefix and end with the suffix, since
+    /// body lines like `i--;` would end the snippet in the other termination modes.
+    pub(crate) fn is_header(&self, line: &str) -> bool {
+        if self.is_unescaped_end_marker(line) {
+            false
+        } else if self.has_custom_delimiters() || self.termination_mode != TerminationMode::EndMarker {
+            self.strip_header_delimiters(line).is_some()
+        } else {
+            line.contains("--")
+        }
+    }
+    
+    /// Reads the header line at the current line of `state`. Adds a warning to `state` if the
+    /// title is empty.
+    fn read_header(&self, line: &str, state: &mut ParseState) -> Header {
+        let mut title = if self.has_custom_delimiters() || self.termination_mode != TerminationMode::EndMarker {
+            self.trim_title(self.strip_header_delimiters(line).unwrap_or_default())
+        } else {
+            self.trim_title(&line.replace("--", ""))
+        };
+        let checksum = read_checksum(&title);
+        if let (Some(_), Some(start)) = (checksum, title.rfind("{crc32:")) {
+            title = self.trim_title(&title[..start]);
+        }
+        if title.is_empty() {
+            state.warnings.push(ParseWarning::EmptyTitle { line: state.line_number });
+        }
+        let line = if self.preserve_headers { Some(line.to_string()) } else { None };
+        Header { title, line, checksum }
+    }
+    
+    /// Returns whether `line` is an end marker, escaped or not.
+    fn is_end_marker(&self, line: &str) -> bool {
+        if self.lenient_end_markers {
+            let normalized: String = line.chars()
+                .filter(|c| !c.is_whitespace())
+                .flat_map(char::to_lowercase)
+                .collect();
+            normalized.contains("--end--")
+        } else {
+            line.contains("-- end --")
+        }
+    }
+    
+    /// Returns whether `line` is an end marker that is not escaped with a backslash, which is
+    /// what ends a snippet body.
+    pub(crate) fn is_unescaped_end_marker(&self, line: &str) -> bool {
+        self.is_end_marker(line) && !line.starts_with('\\')
+    }
+}
+
+/// Returns the checksum at the end of `title`, like `{crc32:abcd1234}`.
+fn read_checksum(title: &str) -> Option<u32> {
+    let start = title.rfind("{crc32:")?;
+    let hex = title[start..].strip_prefix("{crc32:")?.strip_suffix('}')?;
+    if hex.len() != 8 {
+        return None;
+    }
+    u32::from_str_radix(hex, 16).ok()
+}
+
+/// Returns whether `line` starts or ends a front matter block at the top of a file.
+fn is_front_matter_delimiter(line: &str) -> bool {
+    line.trim_end() == "---"
+}
+
+/// Returns the error for front matter at the top of a file that is never closed.
+fn unterminated_front_matter() -> SnippetError {
+    SnippetError::Parse { line: 1, reason: "front matter is never closed by a `---` line".to_string() }
+}
+
+/// Reads the lines of the front matter at the top of a file from `lines`, which are the lines of
+/// the file from its start. The front matter is delimited by `---` lines, which are not included.
+///
+/// Returns `None` if the file does not start with front matter, or an err if the front matter is
+/// never closed.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) fn read_front_matter_lines<L, E, I>(lines: &mut I) -> Result<Option<Vec<String>>, E>
+where
+    L: AsRef<str>,
+    E: From<SnippetError>,
+    I: Iterator<Item = Result<L, E>>
+{
+    match lines.next().transpose()? {
+        Some(line) if is_front_matter_delimiter(line.as_ref()) => {},
+        _ => return Ok(None)
+    }
+    let mut front_matter = Vec::new();
+    for line in lines {
+        let line = line?;
+        let line = line.as_ref();
+        if is_front_matter_delimiter(line) {
+            return Ok(Some(front_matter));
+        }
+        front_matter.push(line.to_string());
+    }
+    Err(unterminated_front_matter().into())
+}
+
+/// Reads the front matter at the top of a file from `lines`, like [`read_front_matter_lines`].
+/// The front matter contains `key: value` lines. Other lines in the front matter are ignored.
+///
+/// Returns an empty `Vec` if the file does not start with front matter.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) fn read_front_matter<L, E, I>(lines: &mut I) -> Result<Vec<(String, String)>, E>
+where
+    L: AsRef<str>,
+    E: From<SnippetError>,
+    I: Iterator<Item = Result<L, E>>
+{
+    let front_matter = read_front_matter_lines(lines)?.unwrap_or_default();
+    Ok(front_matter.iter()
+        .filter_map(|line| line.split_once(':'))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect())
+}
+
+/// Returns whether `line`, outside of a snippet, is a comment in strict mode.
+fn is_comment(line: &str) -> bool {
+    line.trim_start().starts_with('#')
+}
+
+/// Reads the next snippet from `lines`. Lines before the header of the snippet are skipped, and
+/// so is front matter delimited by `---` lines at the top of the file.
+/// In strict mode, skipped lines that are not blank or a comment are added to the stray lines
+/// of `state`.
+///
+/// Returns `Ok(None)` when `lines` runs out before a complete snippet was read, or the err of
+/// the first line that could not be read. Unless the [`TerminationMode`] is
+/// [`EndMarker`](TerminationMode::EndMarker), running out of lines completes the last snippet.
+/// Returns a [`SnippetError::Parse`] if the front matter is never closed.
+pub(crate) fn next_snippet<L, E, I>(lines: &mut I, options: &ParseOptions, state: &mut ParseState) -> Result<Option<Snippet>, E>
+where
+    L: AsRef<str>,
+    E: From<SnippetError>,
+    I: Iterator<Item = Result<L, E>>
+{
+    read_snippet(lines, options, state, true)
+}
+
+/// Reads the next snippet from `lines`, like [`next_snippet`]. When `keep_body` is false, the
+/// lines of the body are skipped rather than kept in memory, and the returned snippet has an
+/// empty body.
+pub(crate) fn read_snippet<L, E, I>(lines: &mut I, options: &ParseOptions, state: &mut ParseState, keep_body: bool) -> Result<Option<Snippet>, E>
+where
+    L: AsRef<str>,
+    E: From<SnippetError>,
+    I: Iterator<Item = Result<L, E>>
+{
+    let mut header: Option<Header> = None;
+    let mut body: Option<String> = None;
+    if let Some(line) = state.pending_header.take() {
+        header = Some(options.read_header(&line, state));
+    }
+    for line in lines {
+        let line = line?;
+        let line = line.as_ref();
+        state.line_number += 1;
+        if state.line_number == 1 && !state.ignore_front_matter && is_front_matter_delimiter(line) {
+            state.in_front_matter = true;
+        } else if state.in_front_matter {
+            state.in_front_matter = !is_front_matter_delimiter(line);
+        } else if header.is_none() {
+            if options.is_header(line) {
+                // Found title
+                header = Some(options.read_header(line, state));
+            } else if is_comment(line) {
+                if let Some(comments) = &mut state.comments {
+                    comments.push(line.to_string());
+                }
+            } else if options.strict && !line.trim().is_empty() {
+                state.stray_lines.push((state.line_number, line.to_string()));
+            }
+        } else if options.is_unescaped_end_marker(line)
+            || (options.termination_mode == TerminationMode::BlankLine && line.trim().is_empty())
+        {
+            return Ok(header.map(|header| finish_snippet(header, body, options, state, keep_body)));
+        } else if options.termination_mode == TerminationMode::NextHeader && options.is_header(line) {
+            state.pending_header = Some(line.to_string());
+            return Ok(header.map(|header| finish_snippet(header, body, options, state, keep_body)));
+        } else if !keep_body {
+            continue;
+        } else if let Some(body) = &mut body {
+            body.push('\n');
+            body.push_str(Snippet::unescape_line(line));
+        } else {
+            body = Some(Snippet::unescape_line(line).to_string());
+        }
+    }
+    if state.in_front_matter {
+        return Err(unterminated_front_matter().into());
+    }
+    if options.termination_mode != TerminationMode::EndMarker {
+        return Ok(header.map(|header| finish_snippet(header, body, options, state, keep_body)));
+    }
+    Ok(None)
+}
+
+/// Creates the snippet that was read by [`read_snippet`]. When checksums are verified and the
+/// body was kept, a checksum that does not match is added to the mismatches of `state`.
+/// Checksums are verified before the body is dedented or trailing newlines are trimmed.
+fn finish_snippet(header: Header, body: Option<String>, options: &ParseOptions, state: &mut ParseState, keep_body: bool) -> Snippet {
+    // Lines are joined as-is, so indentation is preserved exactly
+    let mut snippet = Snippet::new(header.title, body.unwrap_or_default());
+    snippet.header = header.line;
+    if let Some(checksum) = header.checksum {
+        if options.verify_checksums && keep_body && snippet.body_crc32() != checksum {
+            state.checksum_mismatches.push(snippet.title.clone());
+        }
+    }
+    if options.auto_dedent {
+        snippet.s = dedent(&snippet.s);
+    }
+    if options.trim_body_trailing_newline {
+        let trimmed_len = snippet.s.trim_end_matches('\n').len();
+        snippet.s.truncate(trimmed_len);
+    }
+    snippet
+}
+
+/// Removes the leading whitespace that all non-blank lines of `body` have in common. Blank lines
+/// are kept, without the common whitespace if they have it.
+fn dedent(body: &str) -> String {
+    let mut common: Option<&str> = None;
+    for line in body.split('\n').filter(|line| !line.trim().is_empty()) {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        common = Some(match common {
+            None => indent,
+            Some(common) => {
+                let len = common.char_indices()
+                    .zip(indent.chars())
+                    .find(|((_, a), b)| a != b)
+                    .map_or(common.len().min(indent.len()), |((index, _), _)| index);
+                &common[..len]
+            }
+        });
+    }
+    let common = common.unwrap_or_default();
+    let lines: Vec<&str> = body.split('\n').map(|line| line.strip_prefix(common).unwrap_or(line)).collect();
+    lines.join("\n")
+}
+
+/// Finds the problems in the snippet file with the given `lines`, see [`LintIssue`]. Titles are
+/// added to `titles`, so that duplicates across multiple files are found too.
+#[cfg_attr(not(feature = "std"), allow(dead_code))]
+pub(crate) fn lint<L, E, I>(lines: I, options: &ParseOptions, titles: &mut BTreeSet<String>) -> Result<Vec<LintIssue>, E>
+where
+    L: AsRef<str>,
+    I: Iterator<Item = Result<L, E>>
+{
+    let mut issues = Vec::new();
+    let mut state = ParseState::default();
+    // The line number and title of the header of the current snippet, and whether it has a body
+    let mut open: Option<(usize, String, bool)> = None;
+    for (index, line) in lines.enumerate() {
+        let line = line?;
+        let line = line.as_ref();
+        let line_number = index + 1;
+        if line_number == 1 && is_front_matter_delimiter(line) {
+            state.in_front_matter = true;
+        } else if state.in_front_matter {
+            state.in_front_matter = !is_front_matter_delimiter(line);
+        } else if let Some((header_line, title, has_body)) = &mut open {
+            if options.is_unescaped_end_marker(line) {
+                if !*has_body {
+                    issues.push(LintIssue::EmptySnippet { line: *header_line, title: title.clone() });
+                }
+                open = None;
+            } else {
+                *has_body = true;
+            }
+        } else if options.is_end_marker(line) {
+            issues.push(LintIssue::OrphanEndMarker { line: line_number });
+        } else if options.is_header(line) {
+            let title = options.read_header(line, &mut state).title;
+            if !titles.insert(title.clone()) {
+                issues.push(LintIssue::DuplicateTitle { line: line_number, title: title.clone() });
+            }
+            open = Some((line_number, title, false));
+        }
+    }
+    if state.in_front_matter {
+        issues.push(LintIssue::UnterminatedFrontMatter);
+    }
+    Ok(issues)
+}
+
+/// Parses all snippets from the contents of a snippet file.
+///
+/// Text outside of snippets is ignored, and so is a last snippet that is missing its end marker.
+/// A `---` line at the top that starts front matter which is never closed is read as ordinary
+/// text.
+pub fn parse_str(contents: &str) -> Vec<Snippet> {
+    let options = ParseOptions::default();
+    let mut state = ParseState::default();
+    let mut lines = contents.lines().map(Ok::<&str, SnippetError>);
+    let mut snippets = Vec::new();
+    loop {
+        match next_snippet(&mut lines, &options, &mut state) {
+            Ok(Some(snippet)) => snippets.push(snippet),
+            Ok(None) => break,
+            // The front matter is never closed, so read the contents again without it
+            Err(_) => {
+                state = ParseState { ignore_front_matter: true, ..ParseState::default() };
+                lines = contents.lines().map(Ok);
+            }
+        }
+    }
+    snippets
+}
@@ -0,0 +1,1790 @@
+//! Reading and writing snippet files. This requires the `std` feature.
+
+use std::cell::RefCell;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::{Debug, Display, Formatter};
+use std::fs::{self, File};
+use std::io::{self, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::SystemTime;
+
+use crate::parse::{self, LintIssue, ParseOptions, ParseState, ParseWarning, TerminationMode};
+use crate::reader::LineReader;
+use crate::{Snippet, SnippetError};
+
+/// Parses a snippet file, or creates a new struct representing a snippet file.
+#[derive(Debug)]
+pub struct SnippetParser<'a> {
+    paths: Vec<&'a str>,
+    path_index: usize,
+    iter_reader: Option<LineReader>,
+    snippets: Option<Vec<Snippet>>,
+    snippet_index: usize,
+    lines_read: usize,
+    state: ParseState,
+    warnings: Vec<ParseWarning>,
+    options: ParseOptions,
+    cache: RefCell<Option<SnippetCache>>,
+    seekable: bool,
+    /// The files kept open in seekable mode, opened by the first read
+    open_files: RefCell<Vec<File>>
+}
+
+/// The snippets read from the files of a `SnippetParser` by [`get_snippets`](SnippetParser::get_snippets).
+#[derive(Debug)]
+struct SnippetCache {
+    /// The modification time and length of every file when it was read
+    files: Vec<(SystemTime, u64)>,
+    /// The options the files were parsed with
+    options: ParseOptions,
+    snippets: Vec<Snippet>
+}
+
+/// Selects a snippet of a [`SnippetParser`], see [`SnippetParser::select`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SnippetSelector {
+    /// The first snippet with the given title
+    ByTitle(String),
+    /// The snippet at the given index, in the same order as [`SnippetParser::get_snippets`]
+    ByIndex(usize)
+}
+
+/// An edit of the in-memory snippets of a [`SnippetParser`], see [`SnippetParser::apply_edits`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum Edit {
+    /// Adds the snippet, like [`SnippetParser::add_snippet`]
+    Add(Snippet),
+    /// Removes the first snippet with the given title, like [`SnippetParser::remove_snippet`]
+    Remove(String),
+    /// Replaces the string of the first snippet with the given title
+    Update { title: String, body: String },
+    /// Changes the title of the first snippet with title `old` to `new`
+    Rename { old: String, new: String }
+}
+
+// New
+impl<'a> SnippetParser<'a> {
+    /// Creates a new struct representing a snippet file.
+    pub fn new() -> Self {
+        Self { paths: Vec::new(), path_index: 0, iter_reader: None, snippets: None, snippet_index: 0, lines_read: 0, state: ParseState::default(), warnings: Vec::new(), options: ParseOptions::default(), cache: RefCell::new(None), seekable: false, open_files: RefCell::new(Vec::new()) }
+    }
+    
+    /// Reads a snippet file into this struct
+    pub fn read(path: &'a str) -> std::io::Result<Self> {
+        let file = File::open(path);
+        if file.is_err() {
+            return Err(file.err().unwrap());
+        }
+        let reader = LineReader::new(file.unwrap(), &ParseOptions::default());
+        Ok(Self { paths: vec![path], iter_reader: Some(reader), ..Self::new() })
+    }
+    
+    /// Reads a snippet file into this struct, like [`read`](SnippetParser::read), but replaces
+    /// invalid UTF-8 with `U+FFFD REPLACEMENT CHARACTER` instead of returning an err. This is the
+    /// same as enabling [`lossy_utf8`](SnippetParser::lossy_utf8).
+    pub fn read_lossy(path: &'a str) -> io::Result<Self> {
+        Ok(Self::read(path)?.lossy_utf8(true))
+    }
+    
+    /// Reads a snippet file into this struct, or creates a new struct without snippets if the file
+    /// could not be opened, for example because it does not exist yet. To only fall back for a
+    /// missing file, use [`try_load_or_default`](SnippetParser::try_load_or_default).
+    pub fn load_or_default(path: &'a str) -> Self {
+        Self::read(path).unwrap_or_default()
+    }
+    
+    /// Reads a snippet file into this struct, or creates a new struct without snippets if there is
+    /// no file at `path`.
+    ///
+    /// # Errors
+    /// Returns an err if the file exists, but could not be opened.
+    pub fn try_load_or_default(path: &'a str) -> io::Result<Self> {
+        match Self::read(path) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Self::new()),
+            result => result
+        }
+    }
+    
+    /// Reads multiple snippet files into this struct, as if they were a single file.
+    ///
+    /// The files are read in the order of `paths`, and each file is only read once all snippets
+    /// of the previous files have been read.
+    ///
+    /// # Errors
+    /// Returns an err if any of the files could not be opened.
+    pub fn read_many(paths: &[&'a str]) -> std::io::Result<Self> {
+        for path in paths.iter().skip(1) {
+            File::open(path)?;
+        }
+        let mut parser = match paths.first() {
+            Some(path) => Self::read(path)?,
+            None => Self::new()
+        };
+        parser.paths = paths.to_vec();
+        Ok(parser)
+    }
+    
+    /// Creates a new struct representing a snippet file containing the given snippets
+    pub fn from_snippets(snips: Vec<Snippet>) -> Self {
+        Self { snippets: Some(snips), ..Self::new() }
+    }
+    
+    /// Creates a new struct representing a snippet file, with room for `capacity` snippets to be
+    /// added using the `add_snippet` method without reallocating.
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self::from_snippets(Vec::with_capacity(capacity))
+    }
+    
+    /// Creates a new struct containing the paragraphs of `contents` as snippets. Paragraphs are
+    /// separated by one or more blank lines. The first line of a paragraph is the title of its
+    /// snippet, and the other lines are its string.
+    pub fn from_paragraphs(contents: &str) -> Self {
+        let mut snippets = Vec::new();
+        let mut lines = contents.lines();
+        while let Some(title) = lines.by_ref().find(|line| !line.trim().is_empty()) {
+            let body: Vec<&str> = lines.by_ref().take_while(|line| !line.trim().is_empty()).collect();
+            snippets.push(Snippet::new(title.trim().to_string(), body.join("\n")));
+        }
+        Self::from_snippets(snippets)
+    }
+    
+    /// Creates a new struct containing the fenced code blocks of the Markdown in `contents` as
+    /// snippets.
+    ///
+    /// The title of a snippet is the text of the closest heading before its code block, or
+    /// `snippet N` for the `N`th code block if there is no heading before it. The language of a
+    /// snippet is the first word of the info string of its code block, like `rust` in
+    /// ```` ```rust ````. A code block that is not closed is ignored.
+    pub fn from_markdown(contents: &str) -> Self {
+        let mut snippets = Vec::new();
+        let mut heading: Option<&str> = None;
+        let mut lines = contents.lines();
+        while let Some(line) = lines.next() {
+            let line = line.trim_start();
+            if line.starts_with('#') {
+                heading = Some(line.trim_start_matches('#').trim());
+                continue;
+            }
+            let info = match line.strip_prefix("```") {
+                Some(info) => info.trim(),
+                None => continue
+            };
+            
+            let mut body: Vec<&str> = Vec::new();
+            let mut closed = false;
+            for line in lines.by_ref() {
+                if line.trim() == "```" {
+                    closed = true;
+                    break;
+                }
+                body.push(line);
+            }
+            if !closed {
+                break;
+            }
+            
+            let title = match heading {
+                Some(heading) if !heading.is_empty() => heading.to_string(),
+                _ => format!("snippet {}", snippets.len() + 1)
+            };
+            let mut snippet = Snippet::new(title, body.join("\n"));
+            snippet.language = info.split_whitespace().next().map(str::to_string);
+            snippets.push(snippet);
+        }
+        Self::from_snippets(snippets)
+    }
+}
+
+impl Default for SnippetParser<'_> {
+    /// Creates a new struct representing a snippet file, same as [`SnippetParser::new`].
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Clone for SnippetParser<'_> {
+    /// Clones this `SnippetParser`, including its in-memory snippets.
+    ///
+    /// The iteration position is not cloned: the clone re-opens the file at `path` and starts
+    /// iterating from the first snippet again. If the file can no longer be opened, iterating the
+    /// clone only yields the in-memory snippets.
+    fn clone(&self) -> Self {
+        let mut parser = self.reopen_file()
+            .unwrap_or_else(|_| Self { paths: self.paths.clone(), options: self.options.clone(), ..Self::new() });
+        parser.snippets = self.snippets.clone();
+        parser
+    }
+}
+
+// Options
+impl<'a> SnippetParser<'a> {
+    /// When enabled, invisible characters such as zero-width spaces and byte order marks are
+    /// trimmed from titles, in addition to the whitespace that is always trimmed.
+    ///
+    /// This is useful for files containing titles that were copy-pasted from elsewhere.
+    pub fn trim_unicode_titles(mut self, enabled: bool) -> Self {
+        self.options.trim_unicode_titles = enabled;
+        self
+    }
+    
+    /// When enabled, any non-blank line outside of a snippet that is not a comment is an error,
+    /// rather than being ignored. Comments are lines starting with `#`.
+    ///
+    /// The error is a [`SnippetError::StrayLines`] with the stray lines and their line numbers.
+    /// It is returned by
+    /// [`get_snippets`](SnippetParser::get_snippets) and [`iter`](SnippetParser::iter) when reading
+    /// the first snippet after the stray lines, or the end of the file. When used as an iterator,
+    /// the `SnippetParser` skips the rest of the file after the stray lines.
+    ///
+    /// Strict mode also prevents [writing](SnippetParser::write_to) snippets with duplicate titles,
+    /// or titles with line breaks, which are otherwise written as spaces.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+    
+    /// When enabled, end markers are recognized regardless of case and whitespace, so that
+    /// `--end--`, `-- END --` and `-- end--` also end a snippet. By default, only `-- end --` does.
+    pub fn lenient_end_markers(mut self, enabled: bool) -> Self {
+        self.options.lenient_end_markers = enabled;
+        self
+    }
+    
+    /// Limits the length of a line in the file to `max` bytes, excluding the line ending.
+    ///
+    /// Normally a line is read into memory at once, so a file containing an enormous line (e.g.
+    /// a file that is not a snippet file at all) could exhaust memory. With a limit, reading such
+    /// a line returns a [`SnippetError::LineTooLong`] as soon as the limit is exceeded.
+    pub fn max_line_length(mut self, max: usize) -> Self {
+        self.options.max_line_length = Some(max);
+        if let Some(reader) = &mut self.iter_reader {
+            reader.max_line_length = Some(max);
+        }
+        self
+    }
+    
+    /// When enabled, invalid UTF-8 in the file is replaced by `U+FFFD REPLACEMENT CHARACTER`
+    /// instead of causing a [`SnippetError::Parse`], which ends iterating the file.
+    pub fn lossy_utf8(mut self, enabled: bool) -> Self {
+        self.options.lossy_utf8 = enabled;
+        if let Some(reader) = &mut self.iter_reader {
+            reader.lossy_utf8 = enabled;
+        }
+        self
+    }
+    
+    /// When enabled, snippets keep the exact header line they were read from, including any
+    /// extra spacing or decoration around the title. Writing these snippets reproduces the
+    /// original header instead of the normalized `-- title --`.
+    pub fn preserve_headers(mut self, enabled: bool) -> Self {
+        self.options.preserve_headers = enabled;
+        self
+    }
+    
+    /// When enabled, [`get_snippets`](SnippetParser::get_snippets) keeps the files open and
+    /// seeks back to their start to read them again, instead of opening them on every read.
+    ///
+    /// The files are opened by the first read. A file that is replaced afterwards, rather than
+    /// modified in place, is not noticed, since the original file stays open.
+    pub fn seekable(mut self, enabled: bool) -> Self {
+        self.seekable = enabled;
+        if !enabled {
+            self.open_files.get_mut().clear();
+        }
+        self
+    }
+    
+    /// When enabled, the checksums in headers like `-- title {crc32:abcd1234} --` are verified
+    /// while reading. A snippet whose string does not match its checksum results in a
+    /// [`SnippetError::ChecksumMismatch`]. Snippets without a checksum are not verified.
+    ///
+    /// A mismatch does not stop reading: [`iter`](SnippetParser::iter) yields the err in place of
+    /// the snippet and continues with the next one, and iterating the `SnippetParser` itself skips
+    /// the snippet. Methods reading all snippets at once, like
+    /// [`get_snippets`](SnippetParser::get_snippets), return the err.
+    ///
+    /// Checksums are always removed from the titles, whether they are verified or not.
+    pub fn verify_checksums(mut self, enabled: bool) -> Self {
+        self.options.verify_checksums = enabled;
+        self
+    }
+    
+    /// When enabled, snippets are written with a checksum of their string in their header, like
+    /// [`Snippet::to_string_with_checksum`].
+    pub fn write_checksums(mut self, enabled: bool) -> Self {
+        self.options.write_checksums = enabled;
+        self
+    }
+    
+    /// When enabled, trailing newlines are removed from the strings of the snippets read from
+    /// the file. A blank last line in a snippet otherwise ends up as a trailing newline in its
+    /// string, so that, for example, `snippet3 with space` in the sample file is the only snippet
+    /// whose string ends with a newline.
+    pub fn trim_body_trailing_newline(mut self, enabled: bool) -> Self {
+        self.options.trim_body_trailing_newline = enabled;
+        self
+    }
+    
+    /// When enabled, the leading whitespace that all non-blank lines of a snippet have in common
+    /// is removed from its string. This is useful for snippets that are indented in the file.
+    pub fn auto_dedent(mut self, enabled: bool) -> Self {
+        self.options.auto_dedent = enabled;
+        self
+    }
+    
+    /// Sets what ends a snippet besides an end marker. By default, only an end marker does
+    /// ([`TerminationMode::EndMarker`]).
+    pub fn termination_mode(mut self, mode: TerminationMode) -> Self {
+        self.options.termination_mode = mode;
+        self
+    }
+    
+    /// Sets what a header starts with, instead of `--`. Combined with an empty
+    /// [suffix](SnippetParser::title_suffix), this allows headers like `# title`.
+    ///
+    /// Once the prefix or suffix is set, a header is a line that starts with the prefix and ends
+    /// with the suffix, ignoring surrounding whitespace. Snippets are still written with
+    /// `-- title --` headers.
+    pub fn title_prefix(mut self, prefix: &str) -> Self {
+        self.options.title_prefix = Some(prefix.to_string());
+        self
+    }
+    
+    /// Sets what a header ends with, instead of `--`. See
+    /// [`title_prefix`](SnippetParser::title_prefix).
+    pub fn title_suffix(mut self, suffix: &str) -> Self {
+        self.options.title_suffix = Some(suffix.to_string());
+        self
+    }
+}
+
+impl<'a> SnippetParser<'a> {
+    /// Adds a [snippet](crate::Snippet) to this SnippetParser.
+    pub fn add_snippet(&mut self, snip: Snippet) {
+        if let Some(snippets) = &mut self.snippets {
+            snippets.push(snip);
+        } else {
+            self.snippets = Some(vec![snip]);
+        }
+    }
+    
+    /// Returns the snippet with the given title, like [`get_snippet`](SnippetParser::get_snippet).
+    /// If there is no such snippet, the snippet returned by `f` is added like the `add_snippet`
+    /// method does, and a clone of it is returned.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, in which case `f` is not called.
+    ///
+    /// # Panics
+    /// Panics if the snippet returned by `f` does not have the given title, since it would not be
+    /// found by the next call, which would then add another snippet.
+    pub fn get_or_insert_with<F: FnOnce() -> Snippet>(&mut self, title: &str, f: F) -> Result<Snippet, SnippetError> {
+        if let Some(snippet) = self.get_snippet(title)? {
+            return Ok(snippet);
+        }
+        let snippet = f();
+        assert!(snippet.title == title, "get_or_insert_with: the inserted snippet has title {:?} instead of {:?}", snippet.title, title);
+        self.add_snippet(snippet.clone());
+        Ok(snippet)
+    }
+    
+    /// Reads all snippets from the file at `path` with the options of this `SnippetParser`, and
+    /// adds them like the `add_snippet` method does. The file is not read again afterwards.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, like
+    /// [`get_snippets`](SnippetParser::get_snippets). No snippets are added in that case.
+    pub fn append_from(&mut self, path: &str) -> Result<(), SnippetError> {
+        let mut other = SnippetParser::read(path)?;
+        other.options = self.options.clone();
+        for snip in other.get_snippets()? {
+            self.add_snippet(snip);
+        }
+        Ok(())
+    }
+    
+    /// Inserts a [snippet](crate::Snippet) into this SnippetParser at `index`, like
+    /// [`Vec::insert`].
+    ///
+    /// The index is a position among the snippets added using the `add_snippet` method or
+    /// `from_snippets` method. The snippets in the file always come before these snippets, so
+    /// index 0 inserts the snippet right after the snippets in the file.
+    ///
+    /// # Panics
+    /// Panics if `index` is greater than the number of added snippets.
+    pub fn insert_snippet(&mut self, index: usize, snip: Snippet) {
+        let snippets = self.snippets.get_or_insert_with(Vec::new);
+        snippets.insert(index, snip);
+        if index < self.snippet_index {
+            self.snippet_index += 1;
+        }
+    }
+    
+    /// Removes the first snippet with the given title that was added using the `add_snippet`
+    /// method or `from_snippets` method, and returns it. Snippets in the file are not removed.
+    pub fn remove_snippet(&mut self, title: &str) -> Option<Snippet> {
+        let snippets = self.snippets.as_mut()?;
+        let index = snippets.iter().position(|snip| snip.title == title)?;
+        if index < self.snippet_index {
+            self.snippet_index -= 1;
+        }
+        Some(snippets.remove(index))
+    }
+    
+    /// Replaces the first snippet with the given title that was added using the `add_snippet`
+    /// method or `from_snippets` method by `new`, keeping its position. Returns whether a snippet
+    /// was replaced. Snippets in the file are not replaced.
+    pub fn replace_snippet(&mut self, title: &str, new: Snippet) -> bool {
+        match self.snippets.iter_mut().flatten().find(|snip| snip.title == title) {
+            Some(snip) => {
+                *snip = new;
+                true
+            }
+            None => false
+        }
+    }
+    
+    /// Removes and returns an in-memory snippet like [`remove_snippet`](SnippetParser::remove_snippet),
+    /// but treats a missing snippet as an error.
+    ///
+    /// # Errors
+    /// Returns [`SnippetError::NotFound`] if there is no in-memory snippet with the given title.
+    pub fn take_snippet(&mut self, title: &str) -> Result<Snippet, SnippetError> {
+        self.remove_snippet(title).ok_or_else(|| SnippetError::NotFound(title.to_string()))
+    }
+    
+    /// Applies `edits` to the snippets that were added using the `add_snippet` method or
+    /// `from_snippets` method, in order. Either all edits are applied, or none are: when an edit
+    /// fails, the snippets are restored to how they were before the first edit. Snippets in the
+    /// file are not edited.
+    ///
+    /// # Errors
+    /// Returns [`SnippetError::NotFound`] if an edit refers to a title that no in-memory snippet
+    /// has, or [`SnippetError::TitleCollision`] if a snippet is renamed to the title of another
+    /// in-memory snippet.
+    pub fn apply_edits(&mut self, edits: Vec<Edit>) -> Result<(), SnippetError> {
+        let snippets = self.snippets.clone();
+        let snippet_index = self.snippet_index;
+        for edit in edits {
+            if let Err(err) = self.apply_edit(edit) {
+                self.snippets = snippets;
+                self.snippet_index = snippet_index;
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+    
+    /// Applies a single edit for [`apply_edits`](SnippetParser::apply_edits).
+    fn apply_edit(&mut self, edit: Edit) -> Result<(), SnippetError> {
+        match edit {
+            Edit::Add(snip) => self.add_snippet(snip),
+            Edit::Remove(title) => { self.take_snippet(&title)?; },
+            Edit::Update { title, body } => {
+                let index = self.in_memory_position(&title)?;
+                self.snippets.get_or_insert_with(Vec::new)[index].s = body;
+            }
+            Edit::Rename { old, new } => {
+                let index = self.in_memory_position(&old)?;
+                if old != new && self.in_memory_position(&new).is_ok() {
+                    return Err(SnippetError::TitleCollision(new));
+                }
+                let snip = &mut self.snippets.get_or_insert_with(Vec::new)[index];
+                snip.title = new;
+                snip.header = None;
+            }
+        }
+        Ok(())
+    }
+    
+    /// Returns the index of the first in-memory snippet with the given title.
+    fn in_memory_position(&self, title: &str) -> Result<usize, SnippetError> {
+        self.snippets.iter().flatten()
+            .position(|snip| snip.title == title)
+            .ok_or_else(|| SnippetError::NotFound(title.to_string()))
+    }
+    
+    /// Reads all snippets into memory and replaces their titles with the result of `f`, which is
+    /// called with every title in the same order as [`get_snippets`](SnippetParser::get_snippets).
+    /// The renamed snippets become in-memory snippets, so the file is not read again afterwards.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, like
+    /// [`get_snippets`](SnippetParser::get_snippets), or [`SnippetError::TitleCollision`] if `f`
+    /// gives snippets with different titles the same title. No snippets are renamed in
+    /// that case.
+    pub fn rename_all<F: FnMut(&str) -> String>(&mut self, mut f: F) -> Result<(), SnippetError> {
+        let mut snippets = self.get_snippets()?;
+        let mut renamed: HashMap<String, String> = HashMap::new();
+        for snip in &mut snippets {
+            let title = f(&snip.title);
+            match renamed.get(&title) {
+                Some(original) if *original != snip.title => {
+                    return Err(SnippetError::TitleCollision(title));
+                }
+                Some(_) => {},
+                None => { renamed.insert(title.clone(), snip.title.clone()); }
+            }
+            snip.title = title;
+            snip.header = None;
+        }
+        self.materialize()?;
+        self.snippets = Some(snippets);
+        Ok(())
+    }
+    
+    /// Reads all snippets into memory and keeps only the first `n`, in the same order as
+    /// [`get_snippets`](SnippetParser::get_snippets). The kept snippets become in-memory
+    /// snippets, so the file is not read again afterwards.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, like
+    /// [`get_snippets`](SnippetParser::get_snippets). No snippets are dropped in that case.
+    pub fn truncate(&mut self, n: usize) -> Result<(), SnippetError> {
+        self.materialize()?;
+        if let Some(snippets) = &mut self.snippets {
+            snippets.truncate(n);
+        }
+        Ok(())
+    }
+    
+    /// Reads all snippets into memory and shuffles their order using `rng`. The shuffled
+    /// snippets become in-memory snippets, so the file is not read again afterwards.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, like
+    /// [`get_snippets`](SnippetParser::get_snippets). The snippets are not shuffled in that case.
+    #[cfg(feature = "rand")]
+    pub fn shuffle<R: rand::Rng>(&mut self, rng: &mut R) -> Result<(), SnippetError> {
+        use rand::seq::SliceRandom;
+        
+        self.materialize()?;
+        self.snippets.get_or_insert_with(Vec::new).shuffle(rng);
+        Ok(())
+    }
+    
+    /// Gets all snippets from this `SnippetParser`. This means snippets defined by the file at the
+    /// given `path` and files added using the `add_snippet` method or `from_snippets` method.
+    ///
+    /// The snippets read from the files are cached, and the files are only read again once their
+    /// modification time or length changed.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, or in [strict](SnippetParser::strict) mode,
+    /// if the file contains stray lines.
+    pub fn get_snippets(&self) -> Result<Vec<Snippet>, SnippetError> {
+        if !self.paths.is_empty() {
+            let files = self.file_stats()?
+                .into_iter()
+                .map(|metadata| Ok((metadata.modified()?, metadata.len())))
+                .collect::<io::Result<Vec<_>>>()?;
+            let mut cache = self.cache.borrow_mut();
+            let mut snippets = match cache.as_ref() {
+                Some(cache) if cache.files == files && cache.options == self.options => cache.snippets.clone(),
+                _ => {
+                    let snippets = if self.seekable {
+                        self.read_open_files()?
+                    } else {
+                        self.file_snippets()?.collect::<Result<Vec<_>, _>>()?
+                    };
+                    *cache = Some(SnippetCache { files, options: self.options.clone(), snippets: snippets.clone() });
+                    snippets
+                }
+            };
+            snippets.extend(self.snippets.iter().flatten().cloned());
+            Ok(snippets)
+        } else if let Some(snippets) = &self.snippets {
+            Ok(snippets.clone())
+        } else {
+            Ok(Vec::new())
+        }
+    }
+    
+    /// Gets all snippets like [`get_snippets`](SnippetParser::get_snippets), but in reverse order,
+    /// so the last snippet comes first.
+    ///
+    /// All snippets are read into memory first, since the last snippet is only known once the
+    /// whole file has been read.
+    pub fn get_snippets_reversed(&self) -> Result<Vec<Snippet>, SnippetError> {
+        let mut snippets = self.get_snippets()?;
+        snippets.reverse();
+        Ok(snippets)
+    }
+    
+    /// Reads all snippets into memory and returns an iterator over them, which can also iterate
+    /// backwards using [`next_back`](DoubleEndedIterator::next_back).
+    ///
+    /// Since `SnippetParser` is itself an iterator that reads its file forwards, it can't iterate
+    /// backwards. Unlike iterating the parser, this always starts from the first snippet, and
+    /// returns an err if the file could not be read, like [`get_snippets`](SnippetParser::get_snippets).
+    pub fn into_materialized(self) -> Result<std::vec::IntoIter<Snippet>, SnippetError> {
+        Ok(self.get_snippets()?.into_iter())
+    }
+    
+    /// Reads all snippets into a map from their titles to their strings.
+    ///
+    /// When multiple snippets have the same title, the map contains the string of the last one.
+    pub fn into_hashmap(self) -> Result<HashMap<String, String>, SnippetError> {
+        Ok(self.get_snippets()?.into_iter().map(|snippet| (snippet.title, snippet.s)).collect())
+    }
+    
+    /// Merges the changes that `ours` and `theirs` made to the snippets of their common ancestor
+    /// `base`, returning a new `SnippetParser` that only contains in-memory snippets. Snippets are
+    /// matched by title; only the first snippet with a title is considered.
+    ///
+    /// A snippet that was changed, added or removed on one side only gets that change. When both
+    /// sides changed a snippet differently, its string contains both versions between git-style
+    /// conflict markers:
+    ///
+    /// ```text
+    /// <<<<<<< ours
+    /// our string
+    /// =======
+    /// their string
+    /// >>>>>>> theirs
+    /// ```
+    ///
+    /// A side that removed the snippet has an empty version. The merged snippets are in the order
+    /// of `ours`, followed by the snippets that were only added by `theirs`.
+    ///
+    /// # Errors
+    /// Returns an err if any of the files could not be read.
+    pub fn merge_three_way(base: &Self, ours: &Self, theirs: &Self) -> Result<Self, SnippetError> {
+        let by_title = |parser: &Self| -> Result<(Vec<String>, HashMap<String, String>), SnippetError> {
+            let mut titles = Vec::new();
+            let mut bodies = HashMap::new();
+            for snip in parser.get_snippets()? {
+                if let Entry::Vacant(entry) = bodies.entry(snip.title) {
+                    titles.push(entry.key().clone());
+                    entry.insert(snip.s);
+                }
+            }
+            Ok((titles, bodies))
+        };
+        let (_, base) = by_title(base)?;
+        let (our_titles, ours) = by_title(ours)?;
+        let (their_titles, theirs) = by_title(theirs)?;
+        
+        let mut merged = Vec::new();
+        let titles = our_titles.iter().chain(their_titles.iter().filter(|title| !ours.contains_key(*title)));
+        for title in titles {
+            let (base, ours, theirs) = (base.get(title), ours.get(title), theirs.get(title));
+            let body = if ours == theirs || theirs == base {
+                ours.cloned()
+            } else if ours == base {
+                theirs.cloned()
+            } else {
+                Some(format!(
+                    "<<<<<<< ours\n{}\n=======\n{}\n>>>>>>> theirs",
+                    ours.map_or("", String::as_str),
+                    theirs.map_or("", String::as_str)
+                ))
+            };
+            if let Some(body) = body {
+                merged.push(Snippet::new(title.clone(), body));
+            }
+        }
+        Ok(Self::from_snippets(merged))
+    }
+    
+    /// Returns whether this `SnippetParser` and `other` contain the same snippets, regardless of
+    /// their order. A snippet that occurs multiple times has to occur equally often in both.
+    pub fn eq_unordered(&self, other: &SnippetParser) -> Result<bool, SnippetError> {
+        let sorted = |parser: &SnippetParser| -> Result<Vec<(String, String)>, SnippetError> {
+            let mut snippets: Vec<(String, String)> = parser.get_snippets()?
+                .into_iter()
+                .map(|snip| (snip.title, snip.s))
+                .collect();
+            snippets.sort();
+            Ok(snippets)
+        };
+        Ok(sorted(self)? == sorted(other)?)
+    }
+    
+    /// Reads all snippets and distributes them over two new `SnippetParser`s that only contain
+    /// in-memory snippets: the first with the snippets that satisfy `pred`, and the second with
+    /// the others. The order of the snippets is kept.
+    pub fn partition_by<F: Fn(&Snippet) -> bool>(self, pred: F) -> Result<(SnippetParser<'a>, SnippetParser<'a>), SnippetError> {
+        let (matching, other) = self.get_snippets()?.into_iter().partition(|snip| pred(snip));
+        Ok((Self::from_snippets(matching), Self::from_snippets(other)))
+    }
+    
+    /// Reads all snippets and groups them in chunks of `n` snippets, in the same order as
+    /// [`get_snippets`](SnippetParser::get_snippets). The last chunk contains fewer snippets if
+    /// the number of snippets is not a multiple of `n`.
+    ///
+    /// # Panics
+    /// Panics if `n` is 0.
+    pub fn chunks(&self, n: usize) -> Result<Vec<Vec<Snippet>>, SnippetError> {
+        assert!(n != 0, "chunk size must be non-zero");
+        Ok(self.get_snippets()?.chunks(n).map(<[Snippet]>::to_vec).collect())
+    }
+    
+    /// Joins the strings of all snippets with `separator`, in the same order as
+    /// [`get_snippets`](SnippetParser::get_snippets). The titles are left out.
+    pub fn concat_bodies(&self, separator: &str) -> Result<String, SnippetError> {
+        let snippets = self.get_snippets()?;
+        let bodies: Vec<&str> = snippets.iter().map(Snippet::get_string).collect();
+        Ok(bodies.join(separator))
+    }
+    
+    /// Returns the total size of all snippets in bytes, as computed by [`Snippet::byte_len`].
+    pub fn total_bytes(&self) -> Result<usize, SnippetError> {
+        Ok(self.get_snippets()?.iter().map(Snippet::byte_len).sum())
+    }
+    
+    /// Returns up to `limit` snippets, starting at the snippet at index `offset`, in the same order
+    /// as [`get_snippets`](SnippetParser::get_snippets).
+    ///
+    /// The file is read from the start, but the bodies of the snippets before `offset` are skipped
+    /// rather than read into memory, and reading stops once `limit` snippets have been found.
+    pub fn get_snippets_paged(&self, offset: usize, limit: usize) -> Result<Vec<Snippet>, SnippetError> {
+        let mut file_parser = self.reopen_file()?;
+        let mut skipped = 0;
+        while skipped < offset && file_parser.skip_next_snippet()? {
+            skipped += 1;
+        }
+        
+        let mut snippets = Vec::new();
+        while snippets.len() < limit {
+            match file_parser.try_read_next_snippet()? {
+                Some(snippet) => snippets.push(snippet),
+                None => break
+            }
+        }
+        let memory_snippets = self.snippets.iter().flatten()
+            .skip(offset - skipped)
+            .take(limit - snippets.len())
+            .cloned();
+        snippets.extend(memory_snippets);
+        Ok(snippets)
+    }
+    
+    /// Returns the snippets from the first snippet titled `start` up to the next snippet titled
+    /// `end`, in the same order as [`get_snippets`](SnippetParser::get_snippets). When `inclusive`
+    /// is true, the snippets titled `start` and `end` are included, otherwise only the snippets
+    /// between them are.
+    ///
+    /// The file is read lazily, and reading stops at the snippet titled `end`. Returns an empty
+    /// `Vec` if there is no snippet titled `start`, or no snippet titled `end` after it.
+    pub fn get_snippets_between(&self, start: &str, end: &str, inclusive: bool) -> Result<Vec<Snippet>, SnippetError> {
+        let mut between: Option<Vec<Snippet>> = None;
+        for snippet in self.iter()? {
+            let snippet = snippet?;
+            let is_start = between.is_none() && snippet.title == start;
+            if is_start {
+                between = Some(Vec::new());
+            }
+            let snippets = match &mut between {
+                Some(snippets) => snippets,
+                None => continue
+            };
+            let is_end = snippet.title == end;
+            if inclusive || !(is_start || is_end) {
+                snippets.push(snippet);
+            }
+            if is_end {
+                return Ok(between.unwrap_or_default());
+            }
+        }
+        Ok(Vec::new())
+    }
+    
+    /// Returns the number of lines of the file that have been read so far while iterating over
+    /// this `SnippetParser`.
+    ///
+    /// Together with the total number of lines in the file, this can be used to report progress
+    /// when reading a large file.
+    pub fn lines_read(&self) -> usize {
+        self.lines_read
+    }
+    
+    /// Returns the lines of the file at `path` exactly as a parser reads them, without their
+    /// line endings (`\n` or `\r\n`). This is useful to find out why a file is not parsed as
+    /// expected.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be opened. The iterator yields an err if a line
+    /// could not be read, e.g. because it is not valid UTF-8.
+    pub fn raw_lines(path: &str) -> io::Result<impl Iterator<Item = io::Result<String>>> {
+        Ok(LineReader::new(File::open(path)?, &ParseOptions::default()))
+    }
+    
+    /// Checks the files of this `SnippetParser` for problems that don't prevent reading them, but
+    /// probably are mistakes: end markers outside of snippets, snippets without a body and
+    /// duplicate titles. For a parser created using [`read_many`](SnippetParser::read_many), the
+    /// line numbers of the issues count from the start of each file. In-memory snippets are not
+    /// checked.
+    ///
+    /// # Errors
+    /// Returns an err if a file could not be read.
+    pub fn lint(&self) -> Result<Vec<LintIssue>, SnippetError> {
+        let mut titles = BTreeSet::new();
+        let mut issues = Vec::new();
+        for path in &self.paths {
+            let lines = LineReader::new(File::open(path)?, &self.options);
+            issues.extend(parse::lint(lines, &self.options, &mut titles)?);
+        }
+        Ok(issues)
+    }
+    
+    /// Returns the metadata in the front matter at the top of the file, which is delimited by
+    /// `---` lines and contains `key: value` lines:
+    ///
+    /// ```snippet
+    /// ---
+    /// author: Jomy10
+    /// version: 1.0
+    /// ---
+    /// -- my snippet --
+    /// ...
+    /// ```
+    ///
+    /// The front matter is not part of any snippet. Returns an empty map if the file does not
+    /// start with front matter, or if this parser was not created from a file. For a parser
+    /// created using [`read_many`](SnippetParser::read_many), the front matter of the first
+    /// file is returned.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, or [`SnippetError::Parse`] if the front
+    /// matter is never closed.
+    pub fn file_metadata(&self) -> Result<HashMap<String, String>, SnippetError> {
+        match self.paths.first() {
+            Some(path) => {
+                let mut lines = LineReader::new(File::open(path)?, &self.options);
+                Ok(parse::read_front_matter(&mut lines)?.into_iter().collect())
+            },
+            None => Ok(HashMap::new())
+        }
+    }
+    
+    /// Returns the front matter at the top of the first file of this `SnippetParser` with its
+    /// `---` lines, as it is written back by [`save`](SnippetParser::save). Returns an empty
+    /// string if there is no front matter.
+    fn front_matter(&self, eol: &str) -> Result<String, SnippetError> {
+        let front_matter = match self.paths.first() {
+            Some(path) => parse::read_front_matter_lines(&mut LineReader::new(File::open(path)?, &self.options))?,
+            None => None
+        };
+        Ok(match front_matter {
+            Some(lines) => {
+                let mut front_matter = format!("---{}", eol);
+                for line in lines {
+                    front_matter.push_str(&line);
+                    front_matter.push_str(eol);
+                }
+                front_matter + "---" + eol
+            },
+            None => String::new()
+        })
+    }
+    
+    /// Returns the problems found in the file so far while iterating over this `SnippetParser`,
+    /// which did not prevent reading its snippets.
+    pub fn warnings(&self) -> &[ParseWarning] {
+        &self.warnings
+    }
+    
+    /// Returns an iterator over all snippets of this `SnippetParser`, without reading them all
+    /// into memory first.
+    ///
+    /// The file at the given `path` is opened again, so this does not affect the position of the
+    /// `SnippetParser` itself when used as an iterator. Snippets are read from the file one at a
+    /// time as the iterator advances, after which the snippets added using the `add_snippet`
+    /// method or `from_snippets` method follow.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be opened. The iterator yields an err if a line of the
+    /// file could not be read, after which it continues with the in-memory snippets. After the err
+    /// for a checksum that does not match, see [`verify_checksums`](SnippetParser::verify_checksums),
+    /// it continues with the next snippet in the file.
+    pub fn iter(&self) -> Result<impl Iterator<Item = Result<Snippet, SnippetError>> + '_, SnippetError> {
+        let memory_snippets = self.snippets.iter().flatten().cloned().map(Ok);
+        Ok(self.file_snippets()?.chain(memory_snippets))
+    }
+    
+    /// Returns an iterator over all snippets of this `SnippetParser` together with their index,
+    /// starting at 0. Like [`iter`](SnippetParser::iter), the file is read lazily.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be opened. The iterator yields an err if a snippet
+    /// could not be read, like [`iter`](SnippetParser::iter), after which it continues with the
+    /// in-memory snippets. Errs don't have an index, so the indices of the snippets stay
+    /// consecutive.
+    pub fn iter_with_index(&self) -> Result<impl Iterator<Item = Result<(usize, Snippet), SnippetError>> + '_, SnippetError> {
+        let mut index = 0;
+        Ok(self.iter()?.map(move |snippet| {
+            let snippet = snippet?;
+            index += 1;
+            Ok((index - 1, snippet))
+        }))
+    }
+    
+    /// Returns an iterator over the titles and strings of the snippets added using the
+    /// `add_snippet` method or `from_snippets` method, borrowed from this `SnippetParser`.
+    ///
+    /// Snippets in the file are not included, since they are not kept in memory.
+    pub fn iter_titles_and_bodies(&self) -> impl Iterator<Item = (&str, &str)> + '_ {
+        self.snippets.iter().flatten().map(|snip| (snip.title.as_str(), snip.s.as_str()))
+    }
+    
+    /// Restarts iterating this `SnippetParser` from the first snippet, by opening its files again.
+    /// The in-memory snippets are kept, and are iterated again as well.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be opened.
+    pub fn reset(&mut self) -> Result<(), SnippetError> {
+        let mut parser = self.reopen_file()?;
+        parser.snippets = self.snippets.take();
+        parser.cache = self.cache.take().into();
+        parser.open_files = self.open_files.take().into();
+        *self = parser;
+        Ok(())
+    }
+    
+    /// Returns the snippet matching the given title.
+    ///
+    /// # Errors
+    /// Returns an err if the file specified by the path could not be read. Ok otherwise. If there
+    /// was no path specified, then this will always return Ok.
+    ///
+    /// # Optional
+    /// Return `Some(&Snippet)` if the snippet with the specified title could be found, None otherwise
+    pub fn get_snippet(&self, title: &str) -> Result<Option<Snippet>, SnippetError> {
+        self.get_snippet_by(|snippet_title| snippet_title == title)
+    }
+    
+    /// Returns all snippets with the given title, in the same order as
+    /// [`get_snippets`](SnippetParser::get_snippets). Unlike [`get_snippet`](SnippetParser::get_snippet),
+    /// this also returns the snippets after the first one when multiple snippets have the title.
+    pub fn get_all_snippets(&self, title: &str) -> Result<Vec<Snippet>, SnippetError> {
+        Ok(self.get_snippets()?.into_iter().filter(|snippet| snippet.title == title).collect())
+    }
+    
+    /// Returns the snippet matching the given title, like [`get_snippet`](SnippetParser::get_snippet),
+    /// but treats a missing snippet as an error.
+    ///
+    /// # Errors
+    /// Returns [`SnippetError::NotFound`] if there is no snippet with the given title, or
+    /// [`SnippetError::Io`] if the file specified by the path could not be read.
+    pub fn require_snippet(&self, title: &str) -> Result<Snippet, SnippetError> {
+        self.get_snippet(title)?.ok_or_else(|| SnippetError::NotFound(title.to_string()))
+    }
+    
+    /// Returns the titles of all snippets that match the regular expression `pattern`, in the
+    /// same order as [`get_snippets`](SnippetParser::get_snippets). This requires the `regex`
+    /// feature.
+    ///
+    /// Only the headers of the snippets in the file are kept in memory, their bodies are skipped.
+    ///
+    /// # Errors
+    /// Returns [`SnippetError::Regex`] if `pattern` is not a valid regular expression, or
+    /// [`SnippetError::Io`] if the file could not be read.
+    #[cfg(feature = "regex")]
+    pub fn titles_matching(&self, pattern: &str) -> Result<Vec<String>, SnippetError> {
+        let regex = regex::Regex::new(pattern).map_err(SnippetError::Regex)?;
+        let mut titles = Vec::new();
+        if !self.paths.is_empty() {
+            let mut file_parser = self.reopen_file()?;
+            while let Some(snippet) = file_parser.read_file_snippet(false)? {
+                if regex.is_match(&snippet.title) {
+                    titles.push(snippet.title);
+                }
+            }
+        }
+        titles.extend(self.snippets.iter().flatten().filter(|snip| regex.is_match(&snip.title)).map(|snip| snip.title.clone()));
+        Ok(titles)
+    }
+    
+    /// Returns the snippets whose titles fuzzily match `query`, best match first, together with
+    /// their match score. At most `limit` snippets are returned.
+    ///
+    /// A title matches when it contains all characters of `query` in order, ignoring case.
+    /// Matches score higher when the matched characters are consecutive or start a word, and
+    /// lower when they are spread out. Snippets with equal scores keep their original order.
+    pub fn fuzzy_find(&self, query: &str, limit: usize) -> Result<Vec<(Snippet, i64)>, SnippetError> {
+        let mut matches: Vec<(Snippet, i64)> = self.get_snippets()?
+            .into_iter()
+            .filter_map(|snippet| {
+                let score = fuzzy_score(query, &snippet.title)?;
+                Some((snippet, score))
+            })
+            .collect();
+        matches.sort_by(|(_, a), (_, b)| b.cmp(a));
+        matches.truncate(limit);
+        Ok(matches)
+    }
+    
+    /// Returns the titles that are used by more than one snippet, in the order in which they
+    /// first appear. Each title is only returned once.
+    pub fn find_duplicates(&self) -> Result<Vec<String>, SnippetError> {
+        let mut seen = HashSet::new();
+        let mut duplicates = Vec::new();
+        for snippet in self.get_snippets()? {
+            if !seen.insert(snippet.title.clone()) && !duplicates.contains(&snippet.title) {
+                duplicates.push(snippet.title);
+            }
+        }
+        Ok(duplicates)
+    }
+    
+    /// Returns the snippet selected by `selector`, either by its title or by its index.
+    ///
+    /// The index of a snippet is its index in [`get_snippets`](SnippetParser::get_snippets): the
+    /// snippets in the file come first, followed by the in-memory snippets. Only snippets that
+    /// were read successfully have an index.
+    ///
+    /// # Errors
+    /// Returns an err if the file specified by the path could not be read, like
+    /// [`get_snippet`](SnippetParser::get_snippet). When selecting by index, any err before the
+    /// selected snippet is returned, rather than skipped.
+    pub fn select(&self, selector: SnippetSelector) -> Result<Option<Snippet>, SnippetError> {
+        match selector {
+            SnippetSelector::ByTitle(title) => self.get_snippet(&title),
+            SnippetSelector::ByIndex(index) => {
+                let mut snippets = self.iter()?;
+                for _ in 0..index {
+                    if snippets.next().transpose()?.is_none() {
+                        return Ok(None);
+                    }
+                }
+                snippets.next().transpose()
+            }
+        }
+    }
+    
+    /// Returns the number of snippets that satisfy the given predicate. The file is read lazily,
+    /// so the snippets are not all kept in memory at once.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read.
+    pub fn count_by<F: Fn(&Snippet) -> bool>(&self, pred: F) -> Result<usize, SnippetError> {
+        let mut count = 0;
+        for snippet in self.iter()? {
+            if pred(&snippet?) {
+                count += 1;
+            }
+        }
+        Ok(count)
+    }
+    
+    /// Returns the snippet for which `f` returns the largest key, or `None` if there are no
+    /// snippets. If multiple snippets have the largest key, the last one is returned, like
+    /// [`Iterator::max_by_key`]. The file is read lazily, so only the largest snippet so far is
+    /// kept in memory.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read.
+    pub fn max_snippet_by_key<K: Ord, F: FnMut(&Snippet) -> K>(&self, mut f: F) -> Result<Option<Snippet>, SnippetError> {
+        let mut max: Option<(K, Snippet)> = None;
+        for snippet in self.iter()? {
+            let snippet = snippet?;
+            let key = f(&snippet);
+            if max.as_ref().is_none_or(|(max_key, _)| key >= *max_key) {
+                max = Some((key, snippet));
+            }
+        }
+        Ok(max.map(|(_, snippet)| snippet))
+    }
+    
+    /// Returns the snippet for which `f` returns the smallest key, or `None` if there are no
+    /// snippets. If multiple snippets have the smallest key, the first one is returned, like
+    /// [`Iterator::min_by_key`]. The file is read lazily, like
+    /// [`max_snippet_by_key`](SnippetParser::max_snippet_by_key).
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read.
+    pub fn min_snippet_by_key<K: Ord, F: FnMut(&Snippet) -> K>(&self, mut f: F) -> Result<Option<Snippet>, SnippetError> {
+        let mut min: Option<(K, Snippet)> = None;
+        for snippet in self.iter()? {
+            let snippet = snippet?;
+            let key = f(&snippet);
+            if min.as_ref().is_none_or(|(min_key, _)| key < *min_key) {
+                min = Some((key, snippet));
+            }
+        }
+        Ok(min.map(|(_, snippet)| snippet))
+    }
+    
+    /// Returns the first snippet whose string satisfies the given predicate. The file is read
+    /// lazily, and reading stops at the first match.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read before a match was found.
+    pub fn find_by_body<F: Fn(&str) -> bool>(&self, pred: F) -> Result<Option<Snippet>, SnippetError> {
+        for snippet in self.iter()? {
+            let snippet = snippet?;
+            if pred(&snippet.s) {
+                return Ok(Some(snippet));
+            }
+        }
+        Ok(None)
+    }
+    
+    /// Returns the first snippet whose title satisfies the given predicate. This allows for
+    /// custom matching, e.g. case-insensitive or prefix matching.
+    ///
+    /// # Errors
+    /// Returns an err if the file specified by the path could not be read, like
+    /// [`get_snippet`](SnippetParser::get_snippet).
+    pub fn get_snippet_by<F: Fn(&str) -> bool>(&self, pred: F) -> Result<Option<Snippet>, SnippetError> {
+        let snippets = self.get_snippets()?;
+        Ok(snippets.into_iter().find(|snippet| pred(&snippet.title)))
+    }
+}
+
+// Write
+impl<'a> SnippetParser<'a> {
+    /// Writes all snippets of this `SnippetParser` to `w`, in the same format as the `to_string`
+    /// method.
+    ///
+    /// # Errors
+    /// Returns an err if the snippets could not be read or written, or
+    /// [`SnippetError::InvalidTitle`] if a title contains `--`, since it would be read back
+    /// without it. In [strict](SnippetParser::strict) mode, also returns
+    /// [`SnippetError::TitleCollision`] if multiple snippets have the same title, since such a
+    /// file can't be read back unambiguously, or [`SnippetError::InvalidTitle`] if a title
+    /// contains a line break. Nothing is written in that case. Outside of strict mode, line breaks
+    /// in titles are written as spaces, like the `to_string` method of [`Snippet`] does.
+    pub fn write_to<W: Write>(&self, w: &mut W) -> Result<(), SnippetError> {
+        self.write_to_with_eol(w, "\n")
+    }
+    
+    /// Writes all snippets of this `SnippetParser` to `w` like [`write_to`](SnippetParser::write_to),
+    /// but with every line break replaced by `eol`, for example `"\r\n"`.
+    ///
+    /// # Errors
+    /// Returns an err in the same cases as [`write_to`](SnippetParser::write_to).
+    pub fn write_to_with_eol<W: Write>(&self, w: &mut W, eol: &str) -> Result<(), SnippetError> {
+        self.write_snippets(w, &self.get_snippets()?, eol)
+    }
+    
+    /// Writes the snippets of this `SnippetParser` that satisfy `pred` to `w`, like
+    /// [`write_to`](SnippetParser::write_to). Only the snippets that are written are kept in
+    /// memory.
+    ///
+    /// # Errors
+    /// Returns an err in the same cases as [`write_to`](SnippetParser::write_to). In strict mode,
+    /// only the titles of the snippets that are written have to be unique.
+    pub fn write_filtered<W: Write, F: Fn(&Snippet) -> bool>(&self, w: &mut W, pred: F) -> Result<(), SnippetError> {
+        let mut snippets = Vec::new();
+        for snip in self.iter()? {
+            let snip = snip?;
+            if pred(&snip) {
+                snippets.push(snip);
+            }
+        }
+        self.write_snippets(w, &snippets, "\n")
+    }
+    
+    /// Writes `snippets` to `w` with every line break replaced by `eol`, checking for titles
+    /// containing `--` first, and for duplicate titles and titles with line breaks in strict mode.
+    fn write_snippets<W: Write>(&self, w: &mut W, snippets: &[Snippet], eol: &str) -> Result<(), SnippetError> {
+        if self.options.strict {
+            let mut titles = HashSet::new();
+            if let Some(snip) = snippets.iter().find(|snip| !titles.insert(snip.title.as_str())) {
+                return Err(SnippetError::TitleCollision(snip.title.clone()));
+            }
+            if let Some(snip) = snippets.iter().find(|snip| snip.header.is_none() && snip.title.contains(['\r', '\n'])) {
+                return Err(SnippetError::InvalidTitle(snip.title.clone()));
+            }
+        }
+        // `--` is removed from headers when reading, so such a title would silently change
+        if let Some(snip) = snippets.iter().find(|snip| snip.header.is_none() && snip.title.contains("--")) {
+            return Err(SnippetError::InvalidTitle(snip.title.clone()));
+        }
+        for snip in snippets {
+            let contents = if self.options.write_checksums { snip.to_string_with_checksum() } else { snip.to_string() };
+            write!(w, "{}{}", contents.replace('\n', eol), eol)?;
+        }
+        Ok(())
+    }
+    
+    /// Returns all snippets of this `SnippetParser` in the same format as the `to_string` method,
+    /// but with every line break replaced by `eol`.
+    ///
+    /// # Errors
+    /// Returns an err in the same cases as [`write_to`](SnippetParser::write_to).
+    pub fn to_string_with_eol(&self, eol: &str) -> Result<String, SnippetError> {
+        let mut contents = Vec::new();
+        self.write_to_with_eol(&mut contents, eol)?;
+        String::from_utf8(contents).map_err(|err| SnippetError::Io(io::Error::new(io::ErrorKind::InvalidData, err)))
+    }
+    
+    /// Writes all snippets of this `SnippetParser` to the file at `path`, replacing its contents.
+    /// The front matter of the file this parser was read from is written first, see
+    /// [`file_metadata`](SnippetParser::file_metadata).
+    ///
+    /// # Errors
+    /// Returns an err in the same cases as [`write_to`](SnippetParser::write_to), or if the file
+    /// could not be written. The file is left untouched if the snippets are invalid.
+    pub fn save(&self, path: &str) -> Result<(), SnippetError> {
+        Ok(fs::write(path, self.preview_save()?)?)
+    }
+    
+    /// Returns the exact contents that [`save`](SnippetParser::save) would write to the file,
+    /// without writing anything.
+    ///
+    /// # Errors
+    /// Returns an err in the same cases as [`write_to`](SnippetParser::write_to).
+    pub fn preview_save(&self) -> Result<String, SnippetError> {
+        Ok(self.front_matter("\n")? + &self.to_string_with_eol("\n")?)
+    }
+    
+    /// Writes all snippets of this `SnippetParser` back to the file it was read from. This is not
+    /// possible for a parser created using [`read_many`](SnippetParser::read_many).
+    ///
+    /// The snippets are first written to a new temporary file next to the original, with the same
+    /// permissions, which is then renamed over the original file. This way the original file is
+    /// never left half-written. The temporary file is removed if anything fails.
+    ///
+    /// # Errors
+    /// Returns an err if this parser was not created from a file, or in the same cases as
+    /// [`save`](SnippetParser::save).
+    pub fn rewrite_file(&self) -> Result<(), SnippetError> {
+        let path = match self.paths.as_slice() {
+            [path] => Path::new(path),
+            [] => return Err(io::Error::new(io::ErrorKind::InvalidInput, "this parser is not backed by a file").into()),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidInput, "this parser is backed by multiple files").into()),
+        };
+        Ok(write_atomically(path, self.preview_save()?.as_bytes())?)
+    }
+    
+    /// Rewrites the snippet file at `path` in a canonical format: every header is `-- title --`,
+    /// lines have no trailing whitespace, snippets are separated by exactly one blank line, and
+    /// the file ends with a single newline.
+    ///
+    /// Front matter at the top of the file is kept, and so are comments outside of snippets, which
+    /// are written right above the next snippet or at the end of the file. The file is written
+    /// like [`rewrite_file`](SnippetParser::rewrite_file) does, so it is never left half-written.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read or written, or [`SnippetError::StrayLines`] if
+    /// the file contains other text outside of snippets, like [strict](SnippetParser::strict) mode.
+    /// The file is left untouched in that case.
+    pub fn format_file(path: &str) -> Result<(), SnippetError> {
+        let options = ParseOptions { strict: true, ..ParseOptions::default() };
+        let mut state = ParseState::default();
+        state.comments = Some(Vec::new());
+        let mut lines = LineReader::new(File::open(path)?, &options);
+        // Every block is a snippet with the comments above it, or the comments at the end
+        let mut blocks = Vec::new();
+        loop {
+            let snippet = parse::next_snippet(&mut lines, &options, &mut state)?;
+            if !state.stray_lines.is_empty() {
+                return Err(SnippetError::StrayLines(state.stray_lines));
+            }
+            let comments = state.comments.as_mut().map(std::mem::take).unwrap_or_default();
+            let mut block: String = comments.iter().map(|comment| format!("{}\n", comment.trim_end())).collect();
+            let done = snippet.is_none();
+            if let Some(mut snip) = snippet {
+                snip.trim_trailing_whitespace();
+                block.push_str(&format!("{}\n", snip));
+            }
+            if !block.is_empty() {
+                blocks.push(block);
+            }
+            if done {
+                break;
+            }
+        }
+        let contents = SnippetParser::read(path)?.front_matter("\n")? + &blocks.join("\n");
+        Ok(write_atomically(Path::new(path), contents.as_bytes())?)
+    }
+    
+    /// Returns whether the snippet file at `path` survives a round trip: reading it and writing
+    /// it again reproduces the original file.
+    ///
+    /// Differences in line endings and blank lines between snippets are ignored, since those
+    /// don't change the meaning of the file. Any other text outside of snippets is lost when
+    /// writing, so a file containing it does not round-trip.
+    pub fn verify_round_trip(path: &str) -> Result<bool, SnippetError> {
+        Ok(Self::round_trip_diff(path)?.is_none())
+    }
+    
+    /// Returns a diff between the snippet file at `path` and the result of reading and writing it
+    /// again, or `None` if it round-trips (see
+    /// [`verify_round_trip`](SnippetParser::verify_round_trip)).
+    ///
+    /// For every line that differs, the diff contains the line number (starting at 1), followed
+    /// by the original line prefixed with `-` and the written line prefixed with `+`.
+    pub fn round_trip_diff(path: &str) -> Result<Option<String>, SnippetError> {
+        let original = fs::read_to_string(path)?;
+        let options = ParseOptions::default();
+        let mut normalized = Vec::new();
+        let mut in_snippet = false;
+        for line in original.lines() {
+            if in_snippet {
+                in_snippet = !options.is_unescaped_end_marker(line);
+            } else if line.trim().is_empty() {
+                continue;
+            } else {
+                in_snippet = options.is_header(line);
+            }
+            normalized.push(line);
+        }
+        
+        let mut written = Vec::new();
+        SnippetParser::read(path)?.write_to(&mut written)?;
+        let written = String::from_utf8(written).map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        let written: Vec<&str> = written.lines().collect();
+        
+        let mut diff = String::new();
+        for index in 0..normalized.len().max(written.len()) {
+            let original_line = normalized.get(index);
+            let written_line = written.get(index);
+            if original_line == written_line {
+                continue;
+            }
+            diff.push_str(&format!("{}:\n", index + 1));
+            if let Some(line) = original_line {
+                diff.push_str(&format!("-{}\n", line));
+            }
+            if let Some(line) = written_line {
+                diff.push_str(&format!("+{}\n", line));
+            }
+        }
+        Ok(if diff.is_empty() { None } else { Some(diff) })
+    }
+    
+    /// Writes every snippet of this `SnippetParser` to its own file in `dir`, named after the
+    /// [slug](Snippet::title_slug) of its title (`{dir}/{slug}.snip`). The directory is created if
+    /// it does not exist yet.
+    ///
+    /// When multiple snippets have the same slug, a numeric suffix is appended to the later ones
+    /// (`{slug}-2.snip`, `{slug}-3.snip`, ...).
+    ///
+    /// Returns the paths of the written files, in the order of the snippets.
+    pub fn export_each_to_dir(&self, dir: &str) -> Result<Vec<PathBuf>, SnippetError> {
+        fs::create_dir_all(dir)?;
+        let mut used_names: HashSet<String> = HashSet::new();
+        let mut paths = Vec::new();
+        for snip in self.get_snippets()? {
+            let mut slug = snip.title_slug();
+            if slug.is_empty() {
+                slug = "snippet".to_string();
+            }
+            let mut name = slug.clone();
+            let mut suffix = 1;
+            while !used_names.insert(name.clone()) {
+                suffix += 1;
+                name = format!("{}-{}", slug, suffix);
+            }
+            
+            let path = Path::new(dir).join(format!("{}.snip", name));
+            fs::write(&path, format!("{}\n", snip))?;
+            paths.push(path);
+        }
+        Ok(paths)
+    }
+}
+
+/// Iterating a `SnippetParser` reads its file lazily, and is one-shot: once all snippets have been
+/// returned, iterating again yields nothing until the parser is [`reset`](SnippetParser::reset).
+/// To read the snippets without consuming the parser, use [`iter`](SnippetParser::iter) or
+/// [`get_snippets`](SnippetParser::get_snippets), which read the file from the start.
+impl Iterator for SnippetParser<'_> {
+    type Item = Snippet;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.iter_reader.is_some() {
+            if let Some(snippet) = self.read_next_snippet() {
+                Some(snippet)
+            } else {
+                // read next from snippets
+                self.read_next_from_snippets()
+            }
+        } else {
+            // Read next from snippets
+            self.read_next_from_snippets()
+        }
+    }
+    
+    /// The in-memory snippets that have not been returned yet are counted exactly. While there
+    /// may be snippets left in the file, there is no upper bound.
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.snippets.as_ref()
+            .map_or(0, |snippets| snippets.len().saturating_sub(self.snippet_index));
+        if self.iter_reader.is_some() {
+            (remaining, None)
+        } else {
+            (remaining, Some(remaining))
+        }
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn read_next_snippet_test() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let first_snip = "\
+Are we human?
+Or are we dancer?\
+";
+    let second_snip = "\
+This is my church.
+This is where I heal my hurts.";
+    
+    let third_snip = "\
+Never gonna give you up
+Never gonna let you down
+Never gonna run around and desert you
+
+Never gonna make you cry
+Never gonna say goodbye
+Never gonna tell a lie and hurt you
+\
+";
+    let first_read_snip = parser.read_next_snippet().unwrap();
+    let second_read_snip = parser.read_next_snippet().unwrap();
+    let third_read_snip = parser.read_next_snippet().unwrap();
+    
+    assert_eq!(first_snip, first_read_snip.s);
+    assert_eq!(second_snip, second_read_snip.s);
+    assert_eq!(third_snip, third_read_snip.s);
+    assert_eq!("snippet1", first_read_snip.title);
+    assert_eq!("snippet2", second_read_snip.title);
+    assert_eq!("snippet3 with space", third_read_snip.title);
+    assert_eq!(None, parser.read_next_snippet());
+}
+
+#[cfg(test)]
+#[test]
+fn read_next_snippet_test_with_adding_snippet() {
+    // Should not read added snippets
+    
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let first_snip = "\
+Are we human?
+Or are we dancer?\
+";
+    let second_snip = "\
+This is my church.
+This is where I heal my hurts.";
+    
+    let third_snip = "\
+Never gonna give you up
+Never gonna let you down
+Never gonna run around and desert you
+
+Never gonna make you cry
+Never gonna say goodbye
+Never gonna tell a lie and hurt you
+\
+";
+    
+    let fourth_snip = "\
+Are you on the square?
+Are you on the hammer?
+Are you ready to stand right here right now
+Before the devil?
+\
+";
+    let fourth_snippet = Snippet::new("Square Hammer".to_string(), fourth_snip.to_string());
+    
+    parser.add_snippet(fourth_snippet);
+    println!("{:?}", parser);
+    let first_read_snip = parser.read_next_snippet().unwrap();
+    let second_read_snip = parser.read_next_snippet().unwrap();
+    let third_read_snip = parser.read_next_snippet().unwrap();
+    
+    let fourth_read_snip = parser.read_next_snippet();
+    
+    assert_eq!(first_snip, first_read_snip.s);
+    assert_eq!(second_snip, second_read_snip.s);
+    assert_eq!(third_snip, third_read_snip.s);
+    assert_eq!("snippet1", first_read_snip.title);
+    assert_eq!("snippet2", second_read_snip.title);
+    assert_eq!("snippet3 with space", third_read_snip.title);
+    // assert_eq!("Square Hammer", fourth_read_snip.title);
+    // assert_eq!(fourth_snip, fourth_read_snip.s);
+    assert_eq!(None, fourth_read_snip);
+    assert_eq!(None, parser.read_next_snippet());
+}
+
+#[cfg(test)]
+#[test]
+fn with_capacity_test() {
+    let mut parser = SnippetParser::with_capacity(10);
+    let capacity = parser.snippets.as_ref().unwrap().capacity();
+    assert!(capacity >= 10);
+    
+    for i in 0..10 {
+        parser.add_snippet(Snippet::new(format!("snippet{}", i), String::new()));
+    }
+    assert_eq!(capacity, parser.snippets.as_ref().unwrap().capacity());
+    assert_eq!(10, parser.get_snippets().unwrap().len());
+}
+
+/// Scores how well `text` fuzzily matches `query`, or returns `None` if `text` does not contain
+/// all characters of `query` in order. Used by [`SnippetParser::fuzzy_find`].
+fn fuzzy_score(query: &str, text: &str) -> Option<i64> {
+    let mut query_chars = query.chars().flat_map(char::to_lowercase).peekable();
+    let mut score = 0;
+    let mut previous: Option<char> = None;
+    let mut previous_matched = false;
+    for c in text.chars() {
+        let lower = c.to_lowercase().next().unwrap_or(c);
+        match query_chars.peek() {
+            Some(&q) if q == lower => {
+                query_chars.next();
+                score += 16;
+                if previous_matched {
+                    score += 16;
+                }
+                if previous.is_none_or(|p| !p.is_alphanumeric()) {
+                    score += 8;
+                }
+                previous_matched = true;
+            }
+            Some(_) => {
+                score -= 3;
+                previous_matched = false;
+            }
+            None => break
+        }
+        previous = Some(c);
+    }
+    if query_chars.peek().is_none() { Some(score) } else { None }
+}
+
+/// Replaces the contents of the existing file at `path` with `contents`, by writing them to a new
+/// temporary file next to it with the same permissions, which is then renamed over `path`. The
+/// temporary file is removed if anything fails.
+fn write_atomically(path: &Path, contents: &[u8]) -> io::Result<()> {
+    let permissions = fs::metadata(path)?.permissions();
+    let (tmp_path, mut tmp_file) = create_tmp_file(path)?;
+    let result = tmp_file.set_permissions(permissions)
+        .and_then(|_| tmp_file.write_all(contents))
+        .and_then(|_| tmp_file.sync_all())
+        .and_then(|_| fs::rename(&tmp_path, path));
+    if result.is_err() {
+        let _ = fs::remove_file(&tmp_path);
+    }
+    result
+}
+
+/// Creates a new temporary file next to `path` for [`write_atomically`], with a name that is not
+/// in use yet, so that no existing file is overwritten.
+fn create_tmp_file(path: &Path) -> io::Result<(PathBuf, File)> {
+    static COUNTER: AtomicUsize = AtomicUsize::new(0);
+    let file_name = path.file_name().unwrap_or_default().to_string_lossy();
+    loop {
+        let count = COUNTER.fetch_add(1, Ordering::Relaxed);
+        let tmp_path = path.with_file_name(format!(".{}.{}.{}.tmp", file_name, std::process::id(), count));
+        match File::options().write(true).create_new(true).open(&tmp_path) {
+            Ok(file) => return Ok((tmp_path, file)),
+            Err(err) if err.kind() == io::ErrorKind::AlreadyExists => continue,
+            Err(err) => return Err(err)
+        }
+    }
+}
+
+/// Returns whether `err` is a [`SnippetError::ChecksumMismatch`]. The mismatched snippet was read
+/// completely, so the snippets after it can still be read.
+fn is_checksum_mismatch(err: &SnippetError) -> bool {
+    matches!(err, SnippetError::ChecksumMismatch(_))
+}
+
+/// Lazily reads the snippets from the file of a `SnippetParser`, stopping after the first err
+/// that is not a checksum mismatch.
+struct FileSnippets<'a> {
+    parser: Option<SnippetParser<'a>>
+}
+
+impl Iterator for FileSnippets<'_> {
+    type Item = Result<Snippet, SnippetError>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        let result = self.parser.as_mut()?.try_read_next_snippet().transpose();
+        let stop = match &result {
+            Some(Ok(_)) => false,
+            Some(Err(err)) => !is_checksum_mismatch(err),
+            None => true
+        };
+        if stop {
+            self.parser = None;
+        }
+        result
+    }
+}
+
+#[cfg(test)]
+#[test]
+fn file_snippets_reads_lazily() {
+    let path = std::env::temp_dir().join("snippets-rs-file_snippets_reads_lazily.snip");
+    let file_contents: String = (0..1000).map(|i| format!("-- snippet{} --\nbody {}\n-- end --\n", i, i)).collect();
+    fs::write(&path, file_contents).unwrap();
+    
+    let parser = SnippetParser::read(path.to_str().unwrap()).unwrap();
+    let mut file_snippets = parser.file_snippets().unwrap();
+    let first_two: Vec<Snippet> = file_snippets.by_ref().take(2).map(|snip| snip.unwrap()).collect();
+    
+    assert_eq!(vec![Snippet::new("snippet0".to_string(), "body 0".to_string()), Snippet::new("snippet1".to_string(), "body 1".to_string())], first_two);
+    // Only the 6 lines of the first two snippets have been read
+    let remaining_lines = file_snippets.parser.unwrap().iter_reader.unwrap().count();
+    assert_eq!(3000 - 6, remaining_lines);
+    fs::remove_file(path).unwrap();
+}
+
+// next
+impl<'a> SnippetParser<'a> {
+    /// Opens the files at `paths` again, returning a new `SnippetParser` with the same options
+    /// that reads the files from the start. The new parser does not contain any in-memory snippets.
+    fn reopen_file(&self) -> io::Result<Self> {
+        let mut parser = Self::new();
+        if let Some(path) = self.paths.first() {
+            parser.iter_reader = Some(LineReader::new(File::open(path)?, &self.options));
+        }
+        parser.paths = self.paths.clone();
+        parser.options = self.options.clone();
+        parser.seekable = self.seekable;
+        Ok(parser)
+    }
+    
+    /// Reads all snippets into memory as in-memory snippets, and stops reading the files at
+    /// `paths`. Iteration starts from the first snippet again.
+    fn materialize(&mut self) -> Result<(), SnippetError> {
+        let snippets = self.get_snippets()?;
+        self.paths.clear();
+        self.path_index = 0;
+        self.iter_reader = None;
+        self.snippets = Some(snippets);
+        self.snippet_index = 0;
+        self.cache = RefCell::new(None);
+        self.open_files = RefCell::new(Vec::new());
+        Ok(())
+    }
+    
+    /// Returns the metadata of the files at `paths`. In seekable mode, the files are opened and
+    /// kept open if they are not open yet.
+    fn file_stats(&self) -> io::Result<Vec<fs::Metadata>> {
+        if !self.seekable {
+            return self.paths.iter().map(fs::metadata).collect();
+        }
+        let mut open_files = self.open_files.borrow_mut();
+        if open_files.is_empty() {
+            *open_files = self.paths.iter().map(File::open).collect::<io::Result<_>>()?;
+        }
+        open_files.iter().map(File::metadata).collect()
+    }
+    
+    /// Reads all snippets from the files kept open in seekable mode, from their start.
+    fn read_open_files(&self) -> Result<Vec<Snippet>, SnippetError> {
+        let mut snippets = Vec::new();
+        for file in self.open_files.borrow_mut().iter_mut() {
+            file.seek(SeekFrom::Start(0))?;
+            let mut file_parser = Self::new();
+            file_parser.iter_reader = Some(LineReader::new(file.try_clone()?, &self.options));
+            file_parser.options = self.options.clone();
+            while let Some(snippet) = file_parser.try_read_next_snippet()? {
+                snippets.push(snippet);
+            }
+        }
+        Ok(snippets)
+    }
+    
+    /// Returns the paths of the files this parser reads.
+    #[cfg(feature = "notify")]
+    pub(crate) fn paths(&self) -> &[&'a str] {
+        &self.paths
+    }
+    
+    /// Returns an iterator over the snippets in the file, which reads the file from the start.
+    fn file_snippets(&self) -> Result<FileSnippets<'a>, SnippetError> {
+        let parser = if !self.paths.is_empty() { Some(self.reopen_file()?) } else { None };
+        Ok(FileSnippets { parser })
+    }
+    
+    /// Reads the next snippet from the file. This is like a `next` method, but only for
+    /// snippets in the file. A snippet whose checksum does not match is skipped. After any other
+    /// err, the rest of the file is skipped.
+    fn read_next_snippet(&mut self) -> Option<Snippet> {
+        loop {
+            match self.try_read_next_snippet() {
+                Ok(snippet) => return snippet,
+                Err(err) if is_checksum_mismatch(&err) => continue,
+                Err(_) => {
+                    self.iter_reader = None;
+                    return None;
+                }
+            }
+        }
+    }
+    
+    /// Reads the next snippet from the file, like `read_next_snippet`, but returns an err when a
+    /// line of the file could not be read.
+    ///
+    /// When the current file has no more snippets, continues with the next file in `paths`.
+    fn try_read_next_snippet(&mut self) -> Result<Option<Snippet>, SnippetError> {
+        self.read_file_snippet(true)
+    }
+    
+    /// Skips the next snippet in the file without keeping its body in memory. Returns whether a
+    /// snippet was skipped.
+    fn skip_next_snippet(&mut self) -> Result<bool, SnippetError> {
+        Ok(self.read_file_snippet(false)?.is_some())
+    }
+    
+    /// Reads the next snippet from the files. When `keep_body` is false, the body is skipped
+    /// rather than read into memory.
+    fn read_file_snippet(&mut self, keep_body: bool) -> Result<Option<Snippet>, SnippetError> {
+        loop {
+            let lines = match &mut self.iter_reader {
+                Some(lines) => lines,
+                None => return Ok(None)
+            };
+            let lines_read = &mut self.lines_read;
+            let mut lines = lines.inspect(|_| *lines_read += 1);
+            let snippet = parse::read_snippet(&mut lines, &self.options, &mut self.state, keep_body)?;
+            self.warnings.append(&mut self.state.warnings);
+            if !self.state.stray_lines.is_empty() {
+                let stray_lines = std::mem::take(&mut self.state.stray_lines);
+                return Err(SnippetError::StrayLines(stray_lines));
+            }
+            if let Some(title) = self.state.checksum_mismatches.pop() {
+                return Err(SnippetError::ChecksumMismatch(title));
+            }
+            if let Some(snippet) = snippet {
+                return Ok(Some(snippet));
+            }
+            
+            self.path_index += 1;
+            if let Some(path) = self.paths.get(self.path_index) {
+                self.iter_reader = Some(LineReader::new(File::open(path)?, &self.options));
+                self.state = ParseState::default();
+            } else {
+                self.iter_reader = None;
+                return Ok(None);
+            }
+        }
+    }
+    
+    /// Reads the next snippet from the `snippets` field.
+    fn read_next_from_snippets(&mut self) -> Option<Snippet> {
+        if let Some(snippets) = &self.snippets {
+            let snippet = snippets.get(self.snippet_index);
+            self.snippet_index += 1;
+            snippet.cloned()
+        } else {
+            None
+        }
+    }
+}
+
+impl Display for SnippetParser<'_> {
+    /// Formats all snippets in the snippet file format, each followed by a line break.
+    ///
+    /// Formatting fails with [`std::fmt::Error`] if the snippets could not be read, in which case
+    /// the `to_string` method panics. Use [`preview_save`](SnippetParser::preview_save) to get the
+    /// reason instead.
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        for snip in self.get_snippets().map_err(|_| std::fmt::Error)? {
+            writeln!(f, "{}", snip)?;
+        }
+        
+        Ok(())
+    }
+}
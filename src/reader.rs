@@ -0,0 +1,80 @@
+//! Reading the lines of a snippet file. This requires the `std` feature.
+
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+use crate::parse::ParseOptions;
+use crate::SnippetError;
+
+/// Reads the lines of a file, like [`BufRead::lines`], but can limit the length of a line so
+/// that a file with an enormous line can't exhaust memory.
+#[derive(Debug)]
+pub(crate) struct LineReader {
+    reader: BufReader<File>,
+    /// The maximum length of a line in bytes, excluding the line ending
+    pub(crate) max_line_length: Option<usize>,
+    /// Whether invalid UTF-8 is replaced by `U+FFFD REPLACEMENT CHARACTER` instead of being an err
+    pub(crate) lossy_utf8: bool,
+    line_number: usize
+}
+
+impl LineReader {
+    pub(crate) fn new(file: File, options: &ParseOptions) -> Self {
+        Self { reader: BufReader::new(file), max_line_length: options.max_line_length, lossy_utf8: options.lossy_utf8, line_number: 0 }
+    }
+    
+    /// Reads the next line without its line ending, or returns `None` at the end of the file.
+    fn read_line(&mut self) -> io::Result<Option<String>> {
+        let mut line = Vec::new();
+        let mut found_newline = false;
+        loop {
+            let buf = self.reader.fill_buf()?;
+            if buf.is_empty() {
+                if line.is_empty() {
+                    return Ok(None);
+                }
+                break;
+            }
+            let newline = buf.iter().position(|&byte| byte == b'\n');
+            let chunk = &buf[..newline.unwrap_or(buf.len())];
+            if let Some(max) = self.max_line_length {
+                let carriage_return = newline.is_some() && chunk.ends_with(b"\r");
+                if line.len() + chunk.len() - usize::from(carriage_return) > max {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        SnippetError::LineTooLong { line: self.line_number + 1, max }
+                    ));
+                }
+            }
+            line.extend_from_slice(chunk);
+            let consumed = newline.map_or(buf.len(), |newline| newline + 1);
+            self.reader.consume(consumed);
+            if newline.is_some() {
+                found_newline = true;
+                break;
+            }
+        }
+        self.line_number += 1;
+        
+        if found_newline && line.ends_with(b"\r") {
+            line.pop();
+        }
+        if self.lossy_utf8 {
+            return Ok(Some(String::from_utf8_lossy(&line).into_owned()));
+        }
+        String::from_utf8(line)
+            .map(Some)
+            .map_err(|_| io::Error::new(
+                io::ErrorKind::InvalidData,
+                SnippetError::Parse { line: self.line_number, reason: "line is not valid UTF-8".to_string() }
+            ))
+    }
+}
+
+impl Iterator for LineReader {
+    type Item = io::Result<String>;
+    
+    fn next(&mut self) -> Option<Self::Item> {
+        self.read_line().transpose()
+    }
+}
@@ -0,0 +1,507 @@
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::fmt::{Display, Formatter};
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+
+use crate::SnippetError;
+
+#[derive(Clone, Debug)]
+/// Represents a snippet, with a `title` and a `string`
+pub struct Snippet {
+    pub(crate) title: String,
+    pub(crate) s: String,
+    /// The header line this snippet was parsed from, if headers were preserved while parsing
+    pub(crate) header: Option<String>,
+    /// The language of the string, if it was imported from a Markdown code block
+    pub(crate) language: Option<String>
+}
+
+impl PartialEq for Snippet {
+    /// Snippets are equal when their titles, strings and languages are equal, regardless of how
+    /// their headers were formatted.
+    fn eq(&self, other: &Self) -> bool {
+        self.title == other.title && self.s == other.s && self.language == other.language
+    }
+}
+
+impl Display for Snippet {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match &self.header {
+            Some(header) => write!(f, "{}\n{}\n-- end --", self.update_header_checksum(header), Snippet::escape_body(&self.s)),
+            None => write!(f, "-- {} --\n{}\n-- end --", self.header_title(), Snippet::escape_body(&self.s))
+        }
+    }
+}
+
+impl AsRef<str> for Snippet {
+    /// Returns the string (body) of this snippet, not its serialized form with the header.
+    fn as_ref(&self) -> &str {
+        &self.s
+    }
+}
+
+/// The comment syntax used by [`Snippet::commented`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CommentStyle {
+    /// Line comments starting with `//`, like in Rust, C and JavaScript
+    DoubleSlash,
+    /// Line comments starting with `#`, like in Python and shell scripts
+    Hash,
+    /// Line comments starting with `--`, like in SQL and Lua
+    DoubleDash,
+    /// A block comment between `/*` and `*/`
+    Block
+}
+
+/// Returns whether `line`, without any leading backslashes, would be read as an end marker by a
+/// parser with lenient end markers, and thus has to be escaped in a body.
+fn is_end_marker_like(line: &str) -> bool {
+    let normalized: String = line.trim_start_matches('\\')
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+    normalized.contains("--end--")
+}
+
+impl Snippet {
+    /// Creates a new snippet from a title and a string
+    pub fn new(title: String, s: String) -> Snippet {
+        Snippet { title, s, header: None, language: None }
+    }
+    
+    /// Creates a new snippet from a title and a string, like [`new`](Snippet::new), but checks
+    /// that the snippet can be written and read back.
+    ///
+    /// # Errors
+    /// Returns [`SnippetError::EmptyTitle`] if the title is empty or only contains whitespace,
+    /// since such a snippet can't be read back unambiguously.
+    pub fn try_new(title: String, s: String) -> Result<Snippet, SnippetError> {
+        if title.trim().is_empty() {
+            return Err(SnippetError::EmptyTitle);
+        }
+        Ok(Snippet::new(title, s))
+    }
+    
+    /// Appends a string to the snippet
+    pub fn append(&mut self, s: &str) {
+        self.s += s;
+    }
+    
+    /// Appends a string to the snippet and returns the snippet, so calls can be chained
+    pub fn append_str(&mut self, s: &str) -> &mut Self {
+        self.s += s;
+        self
+    }
+    
+    /// Appends the string of `other` to the string of this snippet, separated by `separator`.
+    /// The title of this snippet is kept.
+    pub fn append_snippet(&mut self, other: &Snippet, separator: &str) {
+        self.s += separator;
+        self.s += &other.s;
+    }
+    
+    /// Inserts a string at the start of the snippet. Like [`append`](Snippet::append), no
+    /// newline is added, so include one in `s` to prepend a whole line.
+    pub fn prepend(&mut self, s: &str) {
+        self.s.insert_str(0, s);
+    }
+    
+    /// Replaces the first `count` occurrences of `from` in the string of the snippet with `to`,
+    /// like [`str::replacen`].
+    pub fn replacen_in_body(&mut self, from: &str, to: &str, count: usize) {
+        self.s = self.s.replacen(from, to, count);
+    }
+    
+    /// Removes trailing spaces and tabs from every line of the string of this snippet. The number
+    /// of lines stays the same, and blank lines are kept as empty lines.
+    pub fn trim_trailing_whitespace(&mut self) {
+        let mut trimmed = String::with_capacity(self.s.len());
+        for (index, line) in self.s.split('\n').enumerate() {
+            if index != 0 {
+                trimmed.push('\n');
+            }
+            trimmed.push_str(line.trim_end_matches([' ', '\t']));
+        }
+        self.s = trimmed;
+    }
+    
+    /// Gets the title of the snippet
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+    
+    /// Gets the string from the snippet
+    pub fn get_string(&self) -> &str {
+        &self.s
+    }
+    
+    /// Returns a mutable reference to the string of the snippet, to edit it in place.
+    ///
+    /// The string is kept unescaped: a line like `-- end --` must not be escaped here, since it is
+    /// escaped by [`escape_body`](Snippet::escape_body) when the snippet is written. A line that
+    /// is escaped by hand keeps its backslash when the snippet is read back.
+    pub fn body_mut(&mut self) -> &mut String {
+        &mut self.s
+    }
+    
+    /// Returns this snippet in the same format as the `to_string` method, but with every line
+    /// break replaced by `eol`, for example `"\r\n"`.
+    pub fn to_string_with_eol(&self, eol: &str) -> String {
+        self.to_string().replace('\n', eol)
+    }
+    
+    /// Reduces every run of more than `max` consecutive blank lines in the string of this
+    /// snippet to `max` blank lines. Lines that only contain whitespace count as blank.
+    pub fn collapse_blank_lines(&mut self, max: usize) {
+        let mut collapsed = String::with_capacity(self.s.len());
+        let mut blank_run = 0;
+        let mut first = true;
+        for line in self.s.split('\n') {
+            if line.trim().is_empty() {
+                blank_run += 1;
+                if blank_run > max {
+                    continue;
+                }
+            } else {
+                blank_run = 0;
+            }
+            if !first {
+                collapsed.push('\n');
+            }
+            collapsed.push_str(line);
+            first = false;
+        }
+        self.s = collapsed;
+    }
+    
+    /// Returns the string of this snippet as it should be pasted, without the header and end
+    /// marker. This is the same as [`get_string`](Snippet::get_string).
+    pub fn plain(&self) -> &str {
+        &self.s
+    }
+    
+    /// Returns the string of this snippet like [`plain`](Snippet::plain), but ending with exactly
+    /// one newline, whether the string ends with none or multiple.
+    pub fn plain_with_newline(&self) -> String {
+        let mut plain = self.s.trim_end_matches('\n').to_string();
+        plain.push('\n');
+        plain
+    }
+    
+    /// Returns the title and the string of this snippet, without cloning them.
+    pub fn into_parts(self) -> (String, String) {
+        (self.title, self.s)
+    }
+    
+    /// Returns the original header line of this snippet, if it was read by a parser that
+    /// preserves headers. When present, the header is used as-is when writing the snippet.
+    pub fn header(&self) -> Option<&str> {
+        self.header.as_deref()
+    }
+    
+    /// Returns the language of the string of this snippet, if it was imported from a Markdown
+    /// code block with a language, like ```` ```rust ````. Snippets read from snippet files have no
+    /// language.
+    pub fn language(&self) -> Option<&str> {
+        self.language.as_deref()
+    }
+    
+    /// Returns a fingerprint of the string of this snippet, which can be used to detect whether
+    /// it changed. The title is not included.
+    ///
+    /// The fingerprint is the 64-bit FNV-1a hash of the string, so it is stable across program
+    /// runs and versions of this crate. It is not suitable to protect against tampering.
+    pub fn body_fingerprint(&self) -> u64 {
+        const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+        const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+        self.s.bytes().fold(FNV_OFFSET_BASIS, |hash, byte| (hash ^ u64::from(byte)).wrapping_mul(FNV_PRIME))
+    }
+    
+    /// Returns the CRC-32 (IEEE) checksum of the string of this snippet, as written in headers
+    /// by [`to_string_with_checksum`](Snippet::to_string_with_checksum).
+    pub(crate) fn body_crc32(&self) -> u32 {
+        let crc = self.s.bytes().fold(!0u32, |crc, byte| {
+            (0..8).fold(crc ^ u32::from(byte), |crc, _| {
+                if crc & 1 == 1 { (crc >> 1) ^ 0xEDB8_8320 } else { crc >> 1 }
+            })
+        });
+        !crc
+    }
+    
+    /// Returns this snippet in the same format as the `to_string` method, but with a checksum of
+    /// its string in the header, like `-- title {crc32:abcd1234} --`. A parser that
+    /// [verifies checksums](crate::SnippetParser::verify_checksums) rejects the snippet if its
+    /// string is changed afterwards.
+    pub fn to_string_with_checksum(&self) -> String {
+        format!("-- {} {{crc32:{:08x}}} --\n{}\n-- end --", self.header_title(), self.body_crc32(), Snippet::escape_body(&self.s))
+    }
+    
+    /// Returns the preserved `header` with its checksum, if it has one, replaced by the checksum
+    /// of the current string, since the string may have changed after it was read.
+    fn update_header_checksum(&self, header: &str) -> String {
+        if let Some(start) = header.rfind("{crc32:") {
+            let hex_start = start + "{crc32:".len();
+            let is_checksum = header.get(hex_start..hex_start + 8).is_some_and(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+                && header[hex_start + 8..].starts_with('}');
+            if is_checksum {
+                return format!("{}{:08x}{}", &header[..hex_start], self.body_crc32(), &header[hex_start + 8..]);
+            }
+        }
+        header.to_string()
+    }
+    
+    /// Returns the title as it is written in a header, with line breaks replaced by spaces so
+    /// that the header stays on a single line.
+    fn header_title(&self) -> String {
+        self.title.replace(['\r', '\n'], " ")
+    }
+    
+    /// Returns the size of the title and the string of this snippet in bytes.
+    pub fn byte_len(&self) -> usize {
+        self.title.len() + self.s.len()
+    }
+    
+    /// Returns the body with its placeholders replaced, like in editor snippets.
+    ///
+    /// A placeholder `${name}` is replaced by the value for `name` in `values`. A placeholder can
+    /// also specify a default, `${name:default}`, which is used when `values` does not contain
+    /// `name`. Placeholders without a value or default are left intact.
+    #[cfg(feature = "std")]
+    pub fn expand(&self, values: &HashMap<String, String>) -> String {
+        self.expand_with(|name| values.get(name).cloned()).0
+    }
+    
+    /// Returns the body with its placeholders replaced by environment variables, like
+    /// [`expand`](Snippet::expand) does with a map. A placeholder `${VAR}` is replaced by the value
+    /// of the environment variable `VAR`. Placeholders of variables that are not set, or not valid
+    /// unicode, are left intact unless they specify a default.
+    #[cfg(feature = "std")]
+    pub fn expand_env(&self) -> String {
+        self.expand_with(|name| std::env::var(name).ok()).0
+    }
+    
+    /// Returns the body with its placeholders replaced by environment variables, like
+    /// [`expand_env`](Snippet::expand_env), but treats a variable that is not set as an error.
+    ///
+    /// # Errors
+    /// Returns [`SnippetError::UnsetVariable`] with the name of the first variable that is not set
+    /// and has no default.
+    #[cfg(feature = "std")]
+    pub fn try_expand_env(&self) -> Result<String, SnippetError> {
+        match self.expand_with(|name| std::env::var(name).ok()) {
+            (_, Some(name)) => Err(SnippetError::UnsetVariable(name)),
+            (expanded, None) => Ok(expanded)
+        }
+    }
+    
+    /// Replaces the placeholders in the body by the values returned by `lookup`, see
+    /// [`expand`](Snippet::expand). Also returns the name of the first placeholder that was left
+    /// intact, if any.
+    #[cfg(feature = "std")]
+    fn expand_with<F: Fn(&str) -> Option<String>>(&self, lookup: F) -> (String, Option<String>) {
+        let mut expanded = String::with_capacity(self.s.len());
+        let mut unresolved = None;
+        let mut rest = self.s.as_str();
+        while let Some(start) = rest.find("${") {
+            expanded.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = match rest.find('}') {
+                Some(end) => end,
+                None => break
+            };
+            let (name, default) = match rest[2..end].split_once(':') {
+                Some((name, default)) => (name, Some(default)),
+                None => (&rest[2..end], None)
+            };
+            match lookup(name).as_deref().or(default) {
+                Some(value) => expanded.push_str(value),
+                None => {
+                    expanded.push_str(&rest[..=end]);
+                    unresolved.get_or_insert_with(|| name.to_string());
+                }
+            }
+            rest = &rest[end + 1..];
+        }
+        expanded.push_str(rest);
+        (expanded, unresolved)
+    }
+    
+    /// Returns the body with every line wrapped at word boundaries, so that lines are at most
+    /// `width` characters long.
+    ///
+    /// Blank lines are kept, and lines that are wrapped keep their indentation on every
+    /// continuation line. A single word longer than `width` is put on its own line as-is.
+    pub fn wrap_body(&self, width: usize) -> String {
+        let mut wrapped = String::with_capacity(self.s.len());
+        for (index, line) in self.s.split('\n').enumerate() {
+            if index != 0 {
+                wrapped.push('\n');
+            }
+            if line.chars().count() <= width || line.trim().is_empty() {
+                wrapped.push_str(line);
+                continue;
+            }
+            
+            let indent = &line[..line.len() - line.trim_start().len()];
+            let indent_width = indent.chars().count();
+            let mut line_width = 0;
+            for word in line.split_whitespace() {
+                let word_width = word.chars().count();
+                if line_width == 0 {
+                    wrapped.push_str(indent);
+                    line_width = indent_width;
+                } else if line_width + 1 + word_width <= width {
+                    wrapped.push(' ');
+                    line_width += 1;
+                } else {
+                    wrapped.push('\n');
+                    wrapped.push_str(indent);
+                    line_width = indent_width;
+                }
+                wrapped.push_str(word);
+                line_width += word_width;
+            }
+        }
+        wrapped
+    }
+    
+    /// Splits this snippet into two snippets titled `"{title} (1)"` and `"{title} (2)"`. The
+    /// first contains the first `n` lines of the string, and the second the other lines. If the
+    /// string has `n` lines or fewer, the second snippet has an empty string.
+    pub fn split_at_line(&self, n: usize) -> (Snippet, Snippet) {
+        let lines: Vec<&str> = self.s.split('\n').collect();
+        let (first, second) = lines.split_at(n.min(lines.len()));
+        let part = |number: usize, lines: &[&str]| {
+            let mut part = Snippet::new(format!("{} ({})", self.title, number), lines.join("\n"));
+            part.language = self.language.clone();
+            part
+        };
+        (part(1, first), part(2, second))
+    }
+    
+    /// Returns the segments of a hierarchical title like `rust/http/get`, split on `sep`.
+    pub fn title_segments(&self, sep: char) -> Vec<&str> {
+        self.title.split(sep).collect()
+    }
+    
+    /// Escapes the lines of `body` that would otherwise end the snippet when it is read back,
+    /// like a literal `-- end --` line, by prefixing them with a backslash. This is how bodies
+    /// are written by the `to_string` method, and how the parser expects them.
+    ///
+    /// Lines that already start with backslashes get another one, so that
+    /// [`unescape_body`](Snippet::unescape_body) always restores the original body.
+    pub fn escape_body(body: &str) -> String {
+        let mut escaped = String::with_capacity(body.len());
+        for (index, line) in body.split('\n').enumerate() {
+            if index != 0 {
+                escaped.push('\n');
+            }
+            if is_end_marker_like(line) {
+                escaped.push('\\');
+            }
+            escaped.push_str(line);
+        }
+        escaped
+    }
+    
+    /// Reverses [`escape_body`](Snippet::escape_body).
+    pub fn unescape_body(escaped: &str) -> String {
+        let lines: Vec<&str> = escaped.split('\n').map(Snippet::unescape_line).collect();
+        lines.join("\n")
+    }
+    
+    /// Reverses [`escape_body`](Snippet::escape_body) for a single line.
+    pub(crate) fn unescape_line(line: &str) -> &str {
+        match line.strip_prefix('\\') {
+            Some(unescaped) if is_end_marker_like(unescaped) => unescaped,
+            _ => line
+        }
+    }
+    
+    /// Returns whether the strings of this snippet and `other` are equal when whitespace is
+    /// ignored: leading and trailing whitespace on every line is removed, and every other run of
+    /// whitespace counts as a single space. The lines themselves still have to match. Titles are
+    /// not compared.
+    pub fn body_eq_ignoring_whitespace(&self, other: &Snippet) -> bool {
+        fn normalize(s: &str) -> Vec<Vec<&str>> {
+            s.split('\n').map(|line| line.split_whitespace().collect()).collect()
+        }
+        normalize(&self.s) == normalize(&other.s)
+    }
+    
+    /// Returns whether the string of this snippet contains `query`. When `whole_word` is true,
+    /// `query` only matches when it is not directly preceded or followed by a letter, digit or
+    /// underscore.
+    pub fn body_contains(&self, query: &str, whole_word: bool) -> bool {
+        if !whole_word {
+            return self.s.contains(query);
+        }
+        let is_word_char = |c: char| c.is_alphanumeric() || c == '_';
+        self.s.match_indices(query).any(|(start, matched)| {
+            let before = self.s[..start].chars().next_back();
+            let after = self.s[start + matched.len()..].chars().next();
+            !before.is_some_and(is_word_char) && !after.is_some_and(is_word_char)
+        })
+    }
+    
+    /// Returns the first `max_lines` lines of the string of this snippet, followed by `…` on its
+    /// own line if the string has more lines.
+    pub fn preview(&self, max_lines: usize) -> String {
+        let mut lines = self.s.split('\n');
+        let mut preview: Vec<&str> = lines.by_ref().take(max_lines).collect();
+        if lines.next().is_some() {
+            preview.push("…");
+        }
+        preview.join("\n")
+    }
+    
+    /// Returns the string of this snippet with every line prefixed by its line number, starting
+    /// at `start`. The numbers are right-aligned to at least `width` characters and separated
+    /// from the line by a space.
+    pub fn with_line_numbers(&self, start: usize, width: usize) -> String {
+        let lines: Vec<String> = self.s.split('\n')
+            .enumerate()
+            .map(|(index, line)| format!("{:>width$} {}", start + index, line, width = width))
+            .collect();
+        lines.join("\n")
+    }
+    
+    /// Returns the string of this snippet commented out in the given style. With a line comment
+    /// style, every line is prefixed by the comment marker and a space, or only the marker for a
+    /// blank line. With [`CommentStyle::Block`], the string is put on its own lines between `/*`
+    /// and `*/`.
+    pub fn commented(&self, style: CommentStyle) -> String {
+        let marker = match style {
+            CommentStyle::DoubleSlash => "//",
+            CommentStyle::Hash => "#",
+            CommentStyle::DoubleDash => "--",
+            CommentStyle::Block => return format!("/*\n{}\n*/", self.s)
+        };
+        let lines: Vec<String> = self.s.split('\n')
+            .map(|line| if line.is_empty() { marker.to_string() } else { format!("{} {}", marker, line) })
+            .collect();
+        lines.join("\n")
+    }
+    
+    /// Returns an identifier derived from the title that is safe to use in a filename.
+    ///
+    /// The title is lowercased, and every run of whitespace or other non-alphanumeric characters
+    /// is replaced by a single hyphen. Leading and trailing hyphens are removed.
+    pub fn title_slug(&self) -> String {
+        let mut slug = String::with_capacity(self.title.len());
+        for c in self.title.chars().flat_map(char::to_lowercase) {
+            if c.is_alphanumeric() {
+                slug.push(c);
+            } else if !slug.is_empty() && !slug.ends_with('-') {
+                slug.push('-');
+            }
+        }
+        if slug.ends_with('-') {
+            slug.pop();
+        }
+        slug
+    }
+}
@@ -0,0 +1,68 @@
+//! Arranging snippets with hierarchical titles in a tree. This requires the `std` feature.
+
+use crate::{Snippet, SnippetError, SnippetParser};
+
+/// A node in a tree of snippets with hierarchical titles like `rust/http/get`, created using
+/// [`SnippetParser::tree`].
+///
+/// Every segment of a title is a node, and a snippet is stored in the node of the last segment of
+/// its title. A node can contain both snippets and children, when a title is a prefix of another
+/// title, like `rust/http` and `rust/http/get`.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct SnippetNode {
+    snippets: Vec<Snippet>,
+    children: Vec<(String, SnippetNode)>
+}
+
+impl SnippetNode {
+    /// Returns the snippets whose titles end at this node, in the order they were read.
+    pub fn snippets(&self) -> &[Snippet] {
+        &self.snippets
+    }
+    
+    /// Returns the children of this node with their segments, in the order they first appeared.
+    pub fn children(&self) -> &[(String, SnippetNode)] {
+        &self.children
+    }
+    
+    /// Returns the child of this node for the given segment.
+    pub fn child(&self, segment: &str) -> Option<&SnippetNode> {
+        self.children.iter().find(|(name, _)| name == segment).map(|(_, child)| child)
+    }
+    
+    /// Adds `snippet` to the node at `segments`, below this node.
+    fn insert<'s>(&mut self, mut segments: impl Iterator<Item = &'s str>, snippet: Snippet) {
+        let segment = match segments.next() {
+            Some(segment) => segment,
+            None => {
+                self.snippets.push(snippet);
+                return;
+            }
+        };
+        let index = match self.children.iter().position(|(name, _)| name == segment) {
+            Some(index) => index,
+            None => {
+                self.children.push((segment.to_string(), SnippetNode::default()));
+                self.children.len() - 1
+            }
+        };
+        self.children[index].1.insert(segments, snippet);
+    }
+}
+
+impl SnippetParser<'_> {
+    /// Arranges all snippets in a tree by the segments of their titles, split on `sep`. The
+    /// returned node is the root of the tree, which has no segment.
+    ///
+    /// # Errors
+    /// Returns an err if the file could not be read, like
+    /// [`get_snippets`](SnippetParser::get_snippets).
+    pub fn tree(&self, sep: char) -> Result<SnippetNode, SnippetError> {
+        let mut root = SnippetNode::default();
+        for snippet in self.get_snippets()? {
+            let segments: Vec<String> = snippet.title_segments(sep).into_iter().map(str::to_string).collect();
+            root.insert(segments.iter().map(String::as_str), snippet);
+        }
+        Ok(root)
+    }
+}
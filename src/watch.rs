@@ -0,0 +1,97 @@
+//! Reloading a `SnippetParser` when its files change. This requires the `notify` feature.
+
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::SnippetParser;
+
+/// Watches the files of a [`SnippetParser`] for changes, created using [`SnippetParser::watch`].
+///
+/// Changes are handled while waiting for them using [`wait`](SnippetWatcher::wait) or
+/// [`wait_timeout`](SnippetWatcher::wait_timeout). When a file changes, the parser is reloaded,
+/// after which the callback is invoked with the reloaded parser.
+pub struct SnippetWatcher<'p, 'a, F: FnMut(&SnippetParser)> {
+    parser: &'p mut SnippetParser<'a>,
+    on_change: F,
+    files: Vec<PathBuf>,
+    events: Receiver<notify::Result<Event>>,
+    // Stops watching when dropped
+    _watcher: RecommendedWatcher
+}
+
+impl<'a> SnippetParser<'a> {
+    /// Starts watching the files of this `SnippetParser` for changes. See [`SnippetWatcher`].
+    ///
+    /// The directories containing the files are watched, rather than the files themselves, so
+    /// files that are replaced (e.g. by editors that write to a temporary file first) keep being
+    /// watched.
+    ///
+    /// # Errors
+    /// Returns an err if the files could not be watched.
+    pub fn watch<F: FnMut(&SnippetParser)>(&mut self, on_change: F) -> io::Result<SnippetWatcher<'_, 'a, F>> {
+        let (sender, events) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender).map_err(io::Error::other)?;
+        let mut files = Vec::new();
+        for path in self.paths() {
+            let path = Path::new(path);
+            let dir = match path.parent() {
+                Some(dir) if !dir.as_os_str().is_empty() => dir.canonicalize()?,
+                _ => Path::new(".").canonicalize()?
+            };
+            watcher.watch(&dir, RecursiveMode::NonRecursive).map_err(io::Error::other)?;
+            if let Some(file_name) = path.file_name() {
+                files.push(dir.join(file_name));
+            }
+        }
+        Ok(SnippetWatcher { parser: self, on_change, files, events, _watcher: watcher })
+    }
+}
+
+impl<F: FnMut(&SnippetParser)> SnippetWatcher<'_, '_, F> {
+    /// Blocks until one of the files changes, then reloads the parser and invokes the callback.
+    ///
+    /// # Errors
+    /// Returns an err if the files could not be watched or reloaded.
+    pub fn wait(&mut self) -> io::Result<()> {
+        loop {
+            let event = self.events.recv().map_err(io::Error::other)?;
+            if self.handle(event)? {
+                return Ok(());
+            }
+        }
+    }
+    
+    /// Like [`wait`](SnippetWatcher::wait), but gives up after `timeout`. Returns whether a
+    /// change was handled.
+    pub fn wait_timeout(&mut self, timeout: Duration) -> io::Result<bool> {
+        let deadline = Instant::now() + timeout;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match self.events.recv_timeout(remaining) {
+                Ok(event) => if self.handle(event)? {
+                    return Ok(true);
+                },
+                Err(RecvTimeoutError::Timeout) => return Ok(false),
+                Err(err) => return Err(io::Error::other(err))
+            }
+        }
+    }
+    
+    /// Reloads the parser and invokes the callback if `event` modified one of the files.
+    /// Returns whether it did.
+    fn handle(&mut self, event: notify::Result<Event>) -> io::Result<bool> {
+        let event = event.map_err(io::Error::other)?;
+        let is_change = matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_));
+        if !is_change || !event.paths.iter().any(|path| self.files.contains(path)) {
+            return Ok(false);
+        }
+        
+        self.parser.reset()?;
+        (self.on_change)(self.parser);
+        Ok(true)
+    }
+}
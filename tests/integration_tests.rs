@@ -1,4 +1,19 @@
+#![cfg(feature = "std")]
+
 use snippets_rs::*;
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// Creates a file with the given contents in a fresh temporary directory and returns its path.
+fn temp_file(test_name: &str, file_name: &str, contents: &[u8]) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("snippets-rs-{}", test_name));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(file_name);
+    fs::write(&path, contents).unwrap();
+    path
+}
 
 #[test]
 fn get_string() {
@@ -71,7 +86,7 @@ fn iter_only_manual_adding() {
     let snippet2 = Snippet::new("The day is my enemy".to_string(), "The day is my enemy\nthe night is my friend".to_string());
     let parser = SnippetParser::from_snippets(vec![snippet.clone(), snippet2.clone()]);
     
-    assert_eq!(vec![snippet, snippet2], parser.into_iter().map(|snip| snip).collect::<Vec<Snippet>>());
+    assert_eq!(vec![snippet, snippet2], parser.into_iter().collect::<Vec<Snippet>>());
 }
 
 #[test]
@@ -200,4 +215,1698 @@ It's time the fat cats had a heart attack
 -- end --
 ";
     assert_eq!(file_contents.to_string(), parser.to_string());
-}
\ No newline at end of file
+}
+
+#[test]
+fn append_str_chaining() {
+    let mut snippet = Snippet::new("Title".to_string(), "Hey".to_string());
+    snippet.append_str(" you,").append_str(" out there").append_str(" in the cold");
+    assert_eq!("Hey you, out there in the cold", snippet.get_string());
+}
+
+#[test]
+fn rewrite_file() {
+    let path = temp_file("rewrite_file", "snippets.snip", &fs::read("./tests/snippets/snippet_test.snip").unwrap());
+    let mut parser = SnippetParser::read(path.to_str().unwrap()).unwrap();
+    parser.add_snippet(Snippet::new("Uprising".to_string(), "Rise up and take the power back".to_string()));
+    let expected = parser.to_string();
+    
+    parser.rewrite_file().unwrap();
+    
+    assert_eq!(expected, fs::read_to_string(&path).unwrap());
+    let dir_entries = fs::read_dir(path.parent().unwrap()).unwrap().count();
+    assert_eq!(1, dir_entries);
+    
+    // A file that happens to have the name of a temporary file is left alone
+    let other = path.with_file_name("snippets.snip.tmp");
+    fs::write(&other, "not a temporary file").unwrap();
+    parser.rewrite_file().unwrap();
+    assert_eq!("not a temporary file", fs::read_to_string(&other).unwrap());
+    assert_eq!(2, fs::read_dir(path.parent().unwrap()).unwrap().count());
+}
+
+#[cfg(unix)]
+#[test]
+fn rewrite_file_keeps_permissions() {
+    use std::os::unix::fs::PermissionsExt;
+    
+    let path = temp_file("rewrite_file_keeps_permissions", "snippets.snip", &fs::read("./tests/snippets/snippet_test.snip").unwrap());
+    fs::set_permissions(&path, fs::Permissions::from_mode(0o600)).unwrap();
+    SnippetParser::read(path.to_str().unwrap()).unwrap().rewrite_file().unwrap();
+    assert_eq!(0o600, fs::metadata(&path).unwrap().permissions().mode() & 0o777);
+}
+
+#[test]
+fn unicode_title_trimming() {
+    let path = temp_file("unicode_title_trimming", "snippets.snip", "\
+-- \u{00A0}non-breaking\u{00A0} --
+Padded with non-breaking spaces
+-- end --
+\u{FEFF}-- \u{200B}zero-width\u{200B} --
+Padded with zero-width spaces
+-- end --
+".as_bytes());
+    let path = path.to_str().unwrap();
+    
+    let parser = SnippetParser::read(path).unwrap();
+    assert!(parser.get_snippet("non-breaking").unwrap().is_some());
+    assert!(parser.get_snippet("zero-width").unwrap().is_none());
+    
+    let parser = SnippetParser::read(path).unwrap().trim_unicode_titles(true);
+    assert!(parser.get_snippet("non-breaking").unwrap().is_some());
+    assert_eq!(
+        Snippet::new("zero-width".to_string(), "Padded with zero-width spaces".to_string()),
+        parser.get_snippet("zero-width").unwrap().unwrap()
+    );
+}
+
+#[test]
+fn iter_borrowing() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let extra_snip = Snippet::new("Uprising".to_string(), "Rise up and take the power back".to_string());
+    parser.add_snippet(extra_snip.clone());
+    
+    let snippets = parser.iter().unwrap().collect::<Result<Vec<Snippet>, SnippetError>>().unwrap();
+    assert_eq!(parser.get_snippets().unwrap(), snippets);
+    assert_eq!(Some(&extra_snip), snippets.last());
+    
+    // Iterating with `iter` does not advance the parser itself
+    let first_two = parser.iter().unwrap().take(2).collect::<Result<Vec<Snippet>, SnippetError>>().unwrap();
+    assert_eq!(first_two[0], parser.next().unwrap());
+    assert_eq!(first_two[1], parser.next().unwrap());
+}
+
+#[test]
+fn preserves_indentation() {
+    let mut parser = SnippetParser::read("./tests/snippets/indentation_test.snip").unwrap();
+    let body = "fn main() {\n\tif true {\n\t\t  println!(\"tabs and spaces\");\n  \t}\n}\n    ";
+    
+    assert_eq!(Some(Snippet::new("indented".to_string(), body.to_string())), parser.next());
+}
+
+#[test]
+fn title_slug() {
+    let slug = |title: &str| Snippet::new(title.to_string(), String::new()).title_slug();
+    
+    assert_eq!("snippet3-with-space", slug("snippet3 with space"));
+    assert_eq!("snippet4", slug("snippet4 :)"));
+    assert_eq!("rise-up-take-the-power-back", slug("  Rise up -- take the power back!  "));
+    assert_eq!("café-über-straße", slug("Café Über\tStraße"));
+}
+
+#[test]
+fn export_each_to_dir() {
+    let dir = std::env::temp_dir().join("snippets-rs-export_each_to_dir");
+    let _ = fs::remove_dir_all(&dir);
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    parser.add_snippet(Snippet::new("Snippet1".to_string(), "Same slug as snippet1".to_string()));
+    
+    let paths = parser.export_each_to_dir(dir.to_str().unwrap()).unwrap();
+    
+    assert_eq!(vec![
+        dir.join("snippet1.snip"),
+        dir.join("snippet2.snip"),
+        dir.join("snippet3-with-space.snip"),
+        dir.join("snippet1-2.snip"),
+    ], paths);
+    assert_eq!("-- snippet2 --\nThis is my church.\nThis is where I heal my hurts.\n-- end --\n", fs::read_to_string(&paths[1]).unwrap());
+    assert_eq!(
+        vec![Snippet::new("Snippet1".to_string(), "Same slug as snippet1".to_string())],
+        SnippetParser::read(paths[3].to_str().unwrap()).unwrap().get_snippets().unwrap()
+    );
+}
+
+#[test]
+fn lines_read() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    assert_eq!(0, parser.lines_read());
+    
+    parser.next();
+    assert_eq!(4, parser.lines_read());
+    // The blank line before the header is read as well
+    parser.next();
+    assert_eq!(9, parser.lines_read());
+    parser.next();
+    assert_eq!(21, parser.lines_read());
+    assert_eq!(None, parser.next());
+    assert_eq!(21, parser.lines_read());
+}
+
+#[test]
+fn get_snippet_by() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    
+    let found = parser.get_snippet_by(|title| title.ends_with("with space")).unwrap().unwrap();
+    assert_eq!(parser.get_snippet("snippet3 with space").unwrap().unwrap(), found);
+    assert_eq!(None, parser.get_snippet_by(|title| title.starts_with("Snippet")).unwrap());
+}
+
+#[test]
+fn default() {
+    let mut parser = SnippetParser::default();
+    assert_eq!(Vec::<Snippet>::new(), parser.get_snippets().unwrap());
+    assert_eq!(None, parser.next());
+}
+
+#[test]
+fn clone_resets_iteration() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let extra_snip = Snippet::new("Uprising".to_string(), "Rise up and take the power back".to_string());
+    parser.add_snippet(extra_snip.clone());
+    let first_snip = parser.next().unwrap();
+    let second_snip = parser.next().unwrap();
+    
+    let mut clone = parser.clone();
+    
+    assert_eq!(Some(first_snip), clone.next());
+    assert_eq!(Some(second_snip), clone.next());
+    assert_eq!(parser.next(), clone.next());
+    assert_eq!(Some(extra_snip), clone.next());
+    assert_eq!(None, clone.next());
+}
+
+#[test]
+fn expand() {
+    let snippet = Snippet::new(
+        "Function".to_string(),
+        "fn ${name}(${1:self}) -> ${ret:()} {\n    ${body}\n}".to_string()
+    );
+    let values: HashMap<String, String> = HashMap::from([
+        ("name".to_string(), "get".to_string()),
+        ("ret".to_string(), "String".to_string()),
+    ]);
+    
+    assert_eq!("fn get(self) -> String {\n    ${body}\n}", snippet.expand(&values));
+    assert_eq!("fn ${name}(self) -> () {\n    ${body}\n}", snippet.expand(&HashMap::new()));
+}
+
+#[test]
+fn parse_str_matches_file_parser() {
+    let file_contents = fs::read_to_string("./tests/snippets/snippet_test.snip").unwrap();
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    
+    assert_eq!(parser.get_snippets().unwrap(), parse_str(&file_contents));
+}
+
+#[test]
+fn require_snippet() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    
+    assert_eq!(parser.get_snippet("snippet2").unwrap().unwrap(), parser.require_snippet("snippet2").unwrap());
+    match parser.require_snippet("snippet4") {
+        Err(SnippetError::NotFound(title)) => assert_eq!("snippet4", title),
+        other => panic!("expected a NotFound error, got {:?}", other)
+    }
+}
+
+#[test]
+fn read_many() {
+    let paths = ["./tests/snippets/snippet_test.snip", "./tests/snippets/snippet_test2.snip"];
+    let mut parser = SnippetParser::read_many(&paths).unwrap();
+    
+    let mut expected = SnippetParser::read(paths[0]).unwrap().get_snippets().unwrap();
+    expected.extend(SnippetParser::read(paths[1]).unwrap().get_snippets().unwrap());
+    assert_eq!(5, expected.len());
+    assert_eq!(expected, parser.get_snippets().unwrap());
+    
+    let extra_snip = Snippet::new("Rebel Rebel".to_string(), "Rebel Rebel, you've torn your dress".to_string());
+    parser.add_snippet(extra_snip.clone());
+    expected.push(extra_snip);
+    assert_eq!(expected, parser.collect::<Vec<Snippet>>());
+    
+    assert!(SnippetParser::read_many(&[paths[0], "./tests/snippets/does_not_exist.snip"]).is_err());
+}
+
+#[test]
+fn byte_len() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let snippets = parser.get_snippets().unwrap();
+    
+    assert_eq!(8 + 13 + 1 + 17, snippets[0].byte_len());
+    assert_eq!(8 + 18 + 1 + 30, snippets[1].byte_len());
+    // Two of the lines are empty
+    assert_eq!(19 + 23 + 24 + 37 + 24 + 23 + 35 + 7, snippets[2].byte_len());
+    assert_eq!(288, parser.total_bytes().unwrap());
+    assert_eq!(0, SnippetParser::new().total_bytes().unwrap());
+}
+
+#[test]
+fn preserve_headers_round_trip() {
+    let file_contents = "\
+--   spaced out--
+Lots of space
+-- end --
+--\tdecorated   --
+Tab before the title
+-- end --
+";
+    let path = temp_file("preserve_headers_round_trip", "snippets.snip", file_contents.as_bytes());
+    let path = path.to_str().unwrap();
+    
+    let parser = SnippetParser::read(path).unwrap().preserve_headers(true);
+    let snippets = parser.get_snippets().unwrap();
+    assert_eq!(Some("--\tdecorated   --"), snippets[1].header());
+    assert_eq!(Snippet::new("decorated".to_string(), "Tab before the title".to_string()), snippets[1]);
+    assert_eq!(file_contents, parser.to_string());
+    
+    let parser = SnippetParser::read(path).unwrap();
+    assert_eq!(None, parser.get_snippets().unwrap()[1].header());
+    assert_ne!(file_contents, parser.to_string());
+}
+
+#[test]
+fn fuzzy_find() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    parser.add_snippet(Snippet::new("some new page 1".to_string(), String::new()));
+    
+    let found = parser.fuzzy_find("snp1", 10).unwrap();
+    assert_eq!(2, found.len());
+    assert_eq!(parser.get_snippet("snippet1").unwrap().unwrap(), found[0].0);
+    assert_eq!(parser.get_snippet("some new page 1").unwrap().unwrap(), found[1].0);
+    assert!(found[0].1 > found[1].1);
+    
+    assert_eq!(1, parser.fuzzy_find("snp", 1).unwrap().len());
+    assert!(parser.fuzzy_find("xyz", 10).unwrap().is_empty());
+}
+
+#[cfg(feature = "notify")]
+#[test]
+fn watch() {
+    use std::time::Duration;
+    
+    let path = temp_file("watch", "snippets.snip", b"-- snippet1 --\nBefore\n-- end --\n");
+    let mut parser = SnippetParser::read(path.to_str().unwrap()).unwrap();
+    let mut seen = Vec::new();
+    let mut watcher = parser.watch(|parser| seen.push(parser.get_snippets().unwrap())).unwrap();
+    
+    // Replace the file at once, so the watcher can't observe a half-written file
+    let new_path = path.with_file_name("snippets.snip.new");
+    fs::write(&new_path, "-- snippet1 --\nAfter\n-- end --\n").unwrap();
+    fs::rename(&new_path, &path).unwrap();
+    
+    assert!(watcher.wait_timeout(Duration::from_secs(5)).unwrap());
+    drop(watcher);
+    assert_eq!(vec![Snippet::new("snippet1".to_string(), "After".to_string())], seen[0]);
+    assert_eq!(Some(Snippet::new("snippet1".to_string(), "After".to_string())), parser.next());
+}
+
+#[test]
+fn wrap_body() {
+    let snippet = Snippet::new(
+        "Lyrics".to_string(),
+        "Never gonna run around and desert you\n\n    Never gonna tell a lie and hurt you".to_string()
+    );
+    
+    let wrapped = snippet.wrap_body(20);
+    
+    assert_eq!("\
+Never gonna run
+around and desert
+you
+
+    Never gonna tell
+    a lie and hurt
+    you", wrapped);
+    assert!(wrapped.lines().all(|line| line.chars().count() <= 20));
+    assert_eq!(snippet.get_string(), snippet.wrap_body(80));
+}
+
+#[test]
+fn strict_stray_lines() {
+    let path = "./tests/snippets/stray_line_test.snip";
+    
+    let lenient = SnippetParser::read(path).unwrap();
+    assert_eq!(2, lenient.get_snippets().unwrap().len());
+    
+    let strict = SnippetParser::read(path).unwrap().strict(true);
+    match strict.get_snippets().unwrap_err() {
+        SnippetError::StrayLines(lines) => assert_eq!(vec![(5, "This line is stray".to_string())], lines),
+        other => panic!("expected stray lines, got {:?}", other)
+    }
+    
+    let mut strict = strict;
+    assert!(strict.next().is_some());
+    assert!(strict.next().is_none());
+    assert!(strict.next().is_none());
+    
+    assert!(SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap().strict(true).get_snippets().is_err());
+    
+    // Formatting reports the error instead of panicking
+    let strict = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap().strict(true);
+    let mut formatted = String::new();
+    assert!(std::fmt::write(&mut formatted, format_args!("{}", strict)).is_err());
+}
+
+#[test]
+fn prepend() {
+    let mut snippet = Snippet::new("Title".to_string(), "fn main() {}".to_string());
+    snippet.prepend("// Entry point\n");
+    snippet.prepend("// Generated\n");
+    assert_eq!("// Generated\n// Entry point\nfn main() {}", snippet.get_string());
+}
+
+#[test]
+fn get_snippets_paged() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let all_snippets = parser.get_snippets().unwrap();
+    
+    assert_eq!(&all_snippets[1..2], parser.get_snippets_paged(1, 1).unwrap().as_slice());
+    assert_eq!(&all_snippets[1..], parser.get_snippets_paged(1, 10).unwrap().as_slice());
+    assert!(parser.get_snippets_paged(3, 10).unwrap().is_empty());
+    
+    let extra_snip = Snippet::new("Uprising".to_string(), "Rise up and take the power back".to_string());
+    parser.add_snippet(extra_snip.clone());
+    parser.add_snippet(Snippet::new("Blackstar".to_string(), "I'm a blackstar".to_string()));
+    assert_eq!(vec![all_snippets[2].clone(), extra_snip.clone()], parser.get_snippets_paged(2, 2).unwrap());
+    assert_eq!(vec![extra_snip], parser.get_snippets_paged(3, 1).unwrap());
+}
+
+#[test]
+fn save_title_collision() {
+    let path = temp_file("save_title_collision", "snippets.snip", b"");
+    let path = path.to_str().unwrap();
+    let snippets = vec![
+        Snippet::new("dup".to_string(), "First".to_string()),
+        Snippet::new("dup".to_string(), "Second".to_string()),
+    ];
+    
+    let lenient = SnippetParser::from_snippets(snippets.clone());
+    lenient.save(path).unwrap();
+    assert_eq!("-- dup --\nFirst\n-- end --\n-- dup --\nSecond\n-- end --\n", fs::read_to_string(path).unwrap());
+    
+    fs::write(path, "").unwrap();
+    let strict = SnippetParser::from_snippets(snippets).strict(true);
+    match strict.save(path).unwrap_err() {
+        SnippetError::TitleCollision(title) => assert_eq!("dup", title),
+        other => panic!("expected a title collision, got {:?}", other)
+    }
+    assert_eq!("", fs::read_to_string(path).unwrap());
+}
+
+#[test]
+fn as_ref_str() {
+    fn line_count(s: impl AsRef<str>) -> usize {
+        s.as_ref().lines().count()
+    }
+    
+    let snippet = Snippet::new("Title".to_string(), "This is my church\nThis is where I heal my hurt.".to_string());
+    assert_eq!(2, line_count(&snippet));
+    assert_eq!(snippet.get_string(), AsRef::<str>::as_ref(&snippet));
+}
+
+#[test]
+fn find_duplicates() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    assert!(parser.find_duplicates().unwrap().is_empty());
+    
+    parser.add_snippet(Snippet::new("x".to_string(), "First".to_string()));
+    parser.add_snippet(Snippet::new("x".to_string(), "Second".to_string()));
+    parser.add_snippet(Snippet::new("x".to_string(), "Third".to_string()));
+    assert_eq!(vec!["x".to_string()], parser.find_duplicates().unwrap());
+}
+
+#[test]
+fn lenient_end_markers() {
+    let path = "./tests/snippets/end_marker_test.snip";
+    
+    let lenient = SnippetParser::read(path).unwrap().lenient_end_markers(true);
+    assert_eq!(vec![
+        Snippet::new("no spaces".to_string(), "Ends without spaces".to_string()),
+        Snippet::new("uppercase".to_string(), "Ends in uppercase".to_string()),
+        Snippet::new("one space".to_string(), "Ends with one space".to_string()),
+        Snippet::new("exact".to_string(), "Ends exactly".to_string()),
+    ], lenient.get_snippets().unwrap());
+    
+    // Only the exact end marker ends the first snippet
+    let strict = SnippetParser::read(path).unwrap();
+    let snippets = strict.get_snippets().unwrap();
+    assert_eq!(1, snippets.len());
+    assert!(snippets[0].get_string().ends_with("Ends exactly"));
+}
+
+#[test]
+fn body_fingerprint() {
+    let snippet = Snippet::new("Title".to_string(), "Are we human?".to_string());
+    let renamed = Snippet::new("Other title".to_string(), "Are we human?".to_string());
+    let mut changed = snippet.clone();
+    changed.append("!");
+    
+    assert_eq!(snippet.body_fingerprint(), snippet.clone().body_fingerprint());
+    assert_eq!(snippet.body_fingerprint(), renamed.body_fingerprint());
+    assert_ne!(snippet.body_fingerprint(), changed.body_fingerprint());
+    // FNV-1a of the empty string
+    assert_eq!(0xcbf29ce484222325, Snippet::new("Empty".to_string(), String::new()).body_fingerprint());
+}
+
+#[test]
+fn max_line_length() {
+    let mut file_contents = b"-- snippet1 --\nA normal line\r\n-- end --\n-- snippet2 --\n".to_vec();
+    file_contents.extend(std::iter::repeat_n(b'a', 1 << 20));
+    let path = temp_file("max_line_length", "snippets.snip", &file_contents);
+    
+    let mut parser = SnippetParser::read(path.to_str().unwrap()).unwrap().max_line_length(1024);
+    match parser.get_snippets().unwrap_err() {
+        SnippetError::LineTooLong { line, max } => assert_eq!((5, 1024), (line, max)),
+        other => panic!("expected a line that is too long, got {:?}", other)
+    }
+    
+    assert_eq!(Some(Snippet::new("snippet1".to_string(), "A normal line".to_string())), parser.next());
+    assert_eq!(None, parser.next());
+}
+
+#[test]
+fn into_hashmap() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    parser.add_snippet(Snippet::new("snippet2".to_string(), "Last one wins".to_string()));
+    
+    let map = parser.into_hashmap().unwrap();
+    assert_eq!(3, map.len());
+    assert_eq!("Are we human?\nOr are we dancer?", map["snippet1"]);
+    assert_eq!("Last one wins", map["snippet2"]);
+}
+
+#[test]
+fn size_hint() {
+    let snippets = vec![
+        Snippet::new("Ibiza".to_string(), "What's he doing?".to_string()),
+        Snippet::new("The day is my enemy".to_string(), "The night is my friend".to_string()),
+    ];
+    let mut parser = SnippetParser::from_snippets(snippets);
+    assert_eq!((2, Some(2)), parser.size_hint());
+    parser.next();
+    assert_eq!((1, Some(1)), parser.size_hint());
+    parser.next();
+    parser.next();
+    assert_eq!((0, Some(0)), parser.size_hint());
+    
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    parser.add_snippet(Snippet::new("Uprising".to_string(), "Rise up".to_string()));
+    assert_eq!((1, None), parser.size_hint());
+    parser.by_ref().take(3).for_each(drop);
+    parser.next();
+    assert_eq!((0, Some(0)), parser.size_hint());
+}
+
+#[test]
+fn replacen_in_body() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let mut snippet = parser.get_snippet("snippet3 with space").unwrap().unwrap();
+    
+    snippet.replacen_in_body("Never", "Always", 1);
+    
+    assert!(snippet.get_string().starts_with("Always gonna give you up\nNever gonna let you down"));
+    assert_eq!(5, snippet.get_string().matches("Never").count());
+}
+
+#[test]
+fn verify_round_trip() {
+    assert!(SnippetParser::verify_round_trip("./tests/snippets/snippet_test2.snip").unwrap());
+    assert!(SnippetParser::verify_round_trip("./tests/snippets/indentation_test.snip").unwrap());
+    
+    // The comment between the snippets is lost
+    let path = "./tests/snippets/snippet_test.snip";
+    assert!(!SnippetParser::verify_round_trip(path).unwrap());
+    let diff = SnippetParser::round_trip_diff(path).unwrap().unwrap();
+    assert!(diff.starts_with("9:\n-This is a comment\n+-- snippet3 with space --\n"));
+    
+    // An escaped end marker followed by a blank line stays part of the body
+    let path = temp_file("verify_round_trip", "escaped.snip", b"");
+    let path = path.to_str().unwrap();
+    SnippetParser::from_snippets(vec![Snippet::new("escaped".to_string(), "before\n-- end --\n\nafter".to_string())])
+        .save(path)
+        .unwrap();
+    assert!(SnippetParser::verify_round_trip(path).unwrap());
+    assert_eq!(None, SnippetParser::round_trip_diff(path).unwrap());
+}
+
+#[test]
+fn blank_line_termination_mode() {
+    let path = temp_file("blank_line_termination_mode", "blank.snip", b"\
+-- first --
+First line
+Second line
+
+-- second --
+Only line
+
+
+-- third --
+Last line without end");
+    let snippets = SnippetParser::read(path.to_str().unwrap()).unwrap()
+        .termination_mode(TerminationMode::BlankLine)
+        .get_snippets()
+        .unwrap();
+    
+    assert_eq!(vec![
+        Snippet::new("first".to_string(), "First line\nSecond line".to_string()),
+        Snippet::new("second".to_string(), "Only line".to_string()),
+        Snippet::new("third".to_string(), "Last line without end".to_string())
+    ], snippets);
+    
+    // The end marker after the blank line that ended the snippet does not start a new snippet
+    let path = temp_file("blank_line_termination_mode", "end.snip", b"-- title --\nbody\n\n-- end --\n");
+    let snippets = SnippetParser::read(path.to_str().unwrap()).unwrap()
+        .termination_mode(TerminationMode::BlankLine)
+        .get_snippets()
+        .unwrap();
+    assert_eq!(vec![Snippet::new("title".to_string(), "body".to_string())], snippets);
+}
+
+#[test]
+fn hash_headers() {
+    let path = temp_file("hash_headers", "hash.snip", b"# title\nbody\n\n# other\nmore\nbody\n");
+    let snippets = SnippetParser::read(path.to_str().unwrap()).unwrap()
+        .title_prefix("#")
+        .title_suffix("")
+        .termination_mode(TerminationMode::BlankLine)
+        .get_snippets()
+        .unwrap();
+    
+    assert_eq!(vec![
+        Snippet::new("title".to_string(), "body".to_string()),
+        Snippet::new("other".to_string(), "more\nbody".to_string())
+    ], snippets);
+    
+    // With custom delimiters, a line only containing `--` is not a header
+    let path = temp_file("hash_headers", "mixed.snip", b"-- not a header --\n[title]\nbody\n-- end --\n");
+    let snippets = SnippetParser::read(path.to_str().unwrap()).unwrap()
+        .title_prefix("[")
+        .title_suffix("]")
+        .get_snippets()
+        .unwrap();
+    assert_eq!(vec![Snippet::new("title".to_string(), "body".to_string())], snippets);
+}
+
+#[test]
+fn next_header_termination_mode() {
+    let path = temp_file("next_header_termination_mode", "next.snip", b"\
+-- first --
+First body
+
+-- second --
+Second body
+-- end --
+-- third --
+Third body");
+    let snippets = SnippetParser::read(path.to_str().unwrap()).unwrap()
+        .termination_mode(TerminationMode::NextHeader)
+        .get_snippets()
+        .unwrap();
+    
+    assert_eq!(vec![
+        Snippet::new("first".to_string(), "First body\n".to_string()),
+        Snippet::new("second".to_string(), "Second body".to_string()),
+        Snippet::new("third".to_string(), "Third body".to_string())
+    ], snippets);
+    
+    // Only lines shaped like `-- title --` are headers
+    let path = temp_file("next_header_termination_mode", "dashes.snip", b"-- loop --\nfor (;;) i--;\n-- next --\nbody");
+    let snippets = SnippetParser::read(path.to_str().unwrap()).unwrap()
+        .termination_mode(TerminationMode::NextHeader)
+        .get_snippets()
+        .unwrap();
+    assert_eq!(vec![
+        Snippet::new("loop".to_string(), "for (;;) i--;".to_string()),
+        Snippet::new("next".to_string(), "body".to_string())
+    ], snippets);
+}
+
+#[test]
+fn get_snippets_cache() {
+    let path = temp_file("get_snippets_cache", "cache.snip", b"-- title --\nfirst\n-- end --\n");
+    let parser = SnippetParser::read(path.to_str().unwrap()).unwrap();
+    assert_eq!(vec![Snippet::new("title".to_string(), "first".to_string())], parser.get_snippets().unwrap());
+    
+    // Same modification time and length, so the cached snippets are returned
+    let modified = fs::metadata(&path).unwrap().modified().unwrap();
+    fs::write(&path, b"-- title --\nfixed\n-- end --\n").unwrap();
+    fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    assert_eq!(vec![Snippet::new("title".to_string(), "first".to_string())], parser.get_snippets().unwrap());
+    
+    // Touching the file invalidates the cache
+    let touched = modified + std::time::Duration::from_secs(1);
+    fs::File::options().write(true).open(&path).unwrap().set_modified(touched).unwrap();
+    assert_eq!(vec![Snippet::new("title".to_string(), "fixed".to_string())], parser.get_snippets().unwrap());
+}
+
+#[test]
+fn to_string_with_eol() {
+    let snippet = Snippet::new("title".to_string(), "line 1\nline 2".to_string());
+    assert_eq!("-- title --\r\nline 1\r\nline 2\r\n-- end --", snippet.to_string_with_eol("\r\n"));
+    
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let contents = parser.to_string_with_eol("\r\n").unwrap();
+    assert_eq!(parser.to_string().matches('\n').count(), contents.matches("\r\n").count());
+    assert!(contents.split("\r\n").all(|line| !line.contains('\n')));
+}
+
+#[test]
+fn iterating_is_one_shot_until_reset() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let titles: Vec<String> = parser.by_ref().map(|snip| snip.title().to_string()).collect();
+    assert_eq!(vec!["snippet1", "snippet2", "snippet3 with space"], titles);
+    assert_eq!(None, parser.next());
+    
+    // `iter` reads the file from the start, even after iterating
+    assert_eq!(3, parser.iter().unwrap().count());
+    
+    parser.reset().unwrap();
+    assert_eq!(3, parser.count());
+}
+
+#[test]
+fn warnings() {
+    let path = temp_file("warnings", "empty_title.snip", b"\
+-- first --
+First body
+-- end --
+----
+Body without title
+-- end --
+-- last --
+Last body
+-- end --
+");
+    let mut parser = SnippetParser::read(path.to_str().unwrap()).unwrap();
+    assert_eq!(3, parser.by_ref().count());
+    assert_eq!(&[ParseWarning::EmptyTitle { line: 4 }], parser.warnings());
+}
+
+#[test]
+fn trim_trailing_whitespace() {
+    let mut snippet = Snippet::new("title".to_string(), "  indented  \ttrailing \t\n   \n\nlast  \n".to_string());
+    snippet.trim_trailing_whitespace();
+    assert_eq!("  indented  \ttrailing\n\n\nlast\n", snippet.get_string());
+}
+
+#[test]
+fn concat_bodies() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    parser.add_snippet(Snippet::new("in memory".to_string(), "In memory".to_string()));
+    let expected = "\
+Are we human?
+Or are we dancer?
+---
+This is my church.
+This is where I heal my hurts.
+---
+Never gonna give you up
+Never gonna let you down
+Never gonna run around and desert you
+
+Never gonna make you cry
+Never gonna say goodbye
+Never gonna tell a lie and hurt you
+
+---
+In memory";
+    assert_eq!(expected, parser.concat_bodies("\n---\n").unwrap());
+}
+
+#[test]
+fn try_new() {
+    assert!(matches!(Snippet::try_new("".to_string(), "body".to_string()), Err(SnippetError::EmptyTitle)));
+    assert!(matches!(Snippet::try_new(" \t".to_string(), "body".to_string()), Err(SnippetError::EmptyTitle)));
+    assert_eq!(Snippet::new("title".to_string(), "body".to_string()), Snippet::try_new("title".to_string(), "body".to_string()).unwrap());
+}
+
+#[test]
+fn from_paragraphs() {
+    let contents = "\
+Groceries
+Milk
+Bread
+
+
+Ideas
+  Indented idea
+";
+    let snippets = SnippetParser::from_paragraphs(contents).get_snippets().unwrap();
+    assert_eq!(vec![
+        Snippet::new("Groceries".to_string(), "Milk\nBread".to_string()),
+        Snippet::new("Ideas".to_string(), "  Indented idea".to_string())
+    ], snippets);
+}
+
+#[test]
+fn select() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let snippet2 = Snippet::new("snippet2".to_string(), "This is my church.\nThis is where I heal my hurts.".to_string());
+    
+    assert_eq!(Some(snippet2.clone()), parser.select(SnippetSelector::ByTitle("snippet2".to_string())).unwrap());
+    assert_eq!(Some(snippet2), parser.select(SnippetSelector::ByIndex(1)).unwrap());
+    assert_eq!(None, parser.select(SnippetSelector::ByTitle("snippet4".to_string())).unwrap());
+    assert_eq!(None, parser.select(SnippetSelector::ByIndex(3)).unwrap());
+    
+    // A snippet that could not be read has no index, so its err is returned
+    let path = temp_file("select", "checksums.snip", b"-- corrupted {crc32:00000000} --\nbody\n-- end --\n-- valid --\nbody\n-- end --\n");
+    let parser = SnippetParser::read(path.to_str().unwrap()).unwrap().verify_checksums(true);
+    assert!(matches!(parser.select(SnippetSelector::ByIndex(1)), Err(SnippetError::ChecksumMismatch(title)) if title == "corrupted"));
+    let parser = parser.verify_checksums(false);
+    assert_eq!(Some(Snippet::new("valid".to_string(), "body".to_string())), parser.select(SnippetSelector::ByIndex(1)).unwrap());
+}
+
+#[test]
+fn format_file() {
+    let path = temp_file("format_file", "messy.snip", b"\
+\t--first--   
+body with trailing spaces   
+-- end --  
+-- second --\t
+  indented\t
+
+-- end --
+
+
+
+-- third --
+last
+-- end --");
+    SnippetParser::format_file(path.to_str().unwrap()).unwrap();
+    
+    let expected = "\
+-- first --
+body with trailing spaces
+-- end --
+
+-- second --
+  indented
+
+-- end --
+
+-- third --
+last
+-- end --
+";
+    assert_eq!(expected, fs::read_to_string(&path).unwrap());
+    
+    // Comments are kept above the next snippet, or at the end
+    let path = temp_file("format_file", "comments.snip", b"# greetings   \n-- hello --\nworld\n-- end --\n\n\n# the end\n");
+    SnippetParser::format_file(path.to_str().unwrap()).unwrap();
+    assert_eq!("# greetings\n-- hello --\nworld\n-- end --\n\n# the end\n", fs::read_to_string(&path).unwrap());
+    
+    // Other text outside of snippets is not removed, the file is left untouched instead
+    let contents = b"-- hello --\nworld\n-- end --\nnot a comment\n";
+    let path = temp_file("format_file", "stray.snip", contents);
+    let err = SnippetParser::format_file(path.to_str().unwrap()).unwrap_err();
+    assert!(matches!(err, SnippetError::StrayLines(lines) if lines == vec![(4, "not a comment".to_string())]));
+    assert_eq!(contents.to_vec(), fs::read(&path).unwrap());
+}
+
+#[test]
+fn from_markdown() {
+    let contents = "\
+# Examples
+
+```rust
+fn main() {
+    println!(\"Hello\");
+}
+```
+
+Some text.
+
+```
+no language
+```
+";
+    let snippets = SnippetParser::from_markdown(contents).get_snippets().unwrap();
+    assert_eq!(2, snippets.len());
+    assert_eq!(Some("rust"), snippets[0].language());
+    assert_eq!("fn main() {\n    println!(\"Hello\");\n}", snippets[0].get_string());
+    assert_eq!(None, snippets[1].language());
+    assert_eq!("no language", snippets[1].get_string());
+    assert!(snippets.iter().all(|snip| snip.title() == "Examples"));
+    
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    assert!(parser.get_snippets().unwrap().iter().all(|snip| snip.language().is_none()));
+}
+
+#[test]
+fn partition_by() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    parser.add_snippet(Snippet::new("no digits".to_string(), "body".to_string()));
+    let (with_digit, without_digit) = parser
+        .partition_by(|snip| snip.title().chars().any(|c| c.is_ascii_digit()))
+        .unwrap();
+    
+    let expected = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap().get_snippets().unwrap();
+    assert_eq!(expected, with_digit.get_snippets().unwrap());
+    assert_eq!(vec![Snippet::new("no digits".to_string(), "body".to_string())], without_digit.get_snippets().unwrap());
+}
+
+#[test]
+fn lossy_utf8() {
+    let path = temp_file("lossy_utf8", "invalid.snip", b"-- first --\nvalid\n-- end --\n-- second --\ninvalid \xFF byte\n-- end --\n");
+    let path = path.to_str().unwrap();
+    
+    let err = SnippetParser::read(path).unwrap().get_snippets().unwrap_err();
+    assert!(matches!(err, SnippetError::Parse { line: 5, .. }));
+    
+    let snippets = SnippetParser::read(path).unwrap().lossy_utf8(true).get_snippets().unwrap();
+    assert_eq!(vec![
+        Snippet::new("first".to_string(), "valid".to_string()),
+        Snippet::new("second".to_string(), "invalid \u{FFFD} byte".to_string())
+    ], snippets);
+}
+
+#[test]
+fn get_all_snippets() {
+    let parser = SnippetParser::from_snippets(vec![
+        Snippet::new("dup".to_string(), "first".to_string()),
+        Snippet::new("other".to_string(), "other".to_string()),
+        Snippet::new("dup".to_string(), "second".to_string())
+    ]);
+    assert_eq!(vec![
+        Snippet::new("dup".to_string(), "first".to_string()),
+        Snippet::new("dup".to_string(), "second".to_string())
+    ], parser.get_all_snippets("dup").unwrap());
+    assert!(parser.get_all_snippets("missing").unwrap().is_empty());
+}
+
+#[test]
+fn title_segments() {
+    let snippet = Snippet::new("rust/http/get".to_string(), String::new());
+    assert_eq!(vec!["rust", "http", "get"], snippet.title_segments('/'));
+    assert_eq!(vec!["rust/http/get"], snippet.title_segments('.'));
+}
+
+#[test]
+fn tree() {
+    let parser = SnippetParser::from_snippets(vec![
+        Snippet::new("a/b".to_string(), "b".to_string()),
+        Snippet::new("a/c".to_string(), "c".to_string()),
+        Snippet::new("a".to_string(), "a".to_string()),
+        Snippet::new("d".to_string(), "d".to_string())
+    ]);
+    let root = parser.tree('/').unwrap();
+    assert!(root.snippets().is_empty());
+    assert_eq!(vec!["a", "d"], root.children().iter().map(|(segment, _)| segment.as_str()).collect::<Vec<&str>>());
+    
+    let a = root.child("a").unwrap();
+    assert_eq!(&[Snippet::new("a".to_string(), "a".to_string())], a.snippets());
+    assert_eq!(2, a.children().len());
+    assert_eq!(&[Snippet::new("a/c".to_string(), "c".to_string())], a.child("c").unwrap().snippets());
+    assert!(a.child("c").unwrap().children().is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn seekable() {
+    let path = temp_file("seekable", "seekable.snip", b"-- title --\nfirst\n-- end --\n");
+    let parser = SnippetParser::read(path.to_str().unwrap()).unwrap().seekable(true);
+    let first = parser.get_snippets().unwrap();
+    assert_eq!(vec![Snippet::new("title".to_string(), "first".to_string())], first);
+    assert_eq!(first, parser.get_snippets().unwrap());
+    
+    // Modifying the file in place is noticed
+    fs::write(&path, b"-- title --\nsecond\n-- end --\n").unwrap();
+    let second = parser.get_snippets().unwrap();
+    assert_eq!(vec![Snippet::new("title".to_string(), "second".to_string())], second);
+    
+    // The file is not opened again, so it can still be read after removing it
+    fs::remove_file(&path).unwrap();
+    assert_eq!(second, parser.get_snippets().unwrap());
+    assert!(SnippetParser::read(path.to_str().unwrap()).is_err());
+}
+
+#[test]
+fn escape_body() {
+    let bodies = [
+        "-- end --",
+        "before\n-- end --\nafter",
+        "  -- END --\n\\-- end --\n\\\\--end--",
+        "no markers -- here\n\\backslash"
+    ];
+    for body in bodies {
+        assert_eq!(body, Snippet::unescape_body(&Snippet::escape_body(body)));
+    }
+    assert_eq!("before\n\\-- end --\nafter", Snippet::escape_body("before\n-- end --\nafter"));
+    assert_eq!("no markers -- here\n\\backslash", Snippet::escape_body("no markers -- here\n\\backslash"));
+    
+    // The parser reads escaped bodies back
+    let snippets: Vec<Snippet> = bodies.iter()
+        .enumerate()
+        .map(|(index, body)| Snippet::new(format!("snippet{}", index), body.to_string()))
+        .collect();
+    let contents = SnippetParser::from_snippets(snippets.clone()).to_string();
+    assert_eq!(snippets, parse_str(&contents));
+    
+    let path = temp_file("escape_body", "escaped.snip", contents.as_bytes());
+    let parser = SnippetParser::read(path.to_str().unwrap()).unwrap().lenient_end_markers(true);
+    assert_eq!(snippets, parser.get_snippets().unwrap());
+}
+
+#[test]
+fn get_snippets_reversed() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let snippets = parser.get_snippets_reversed().unwrap();
+    let titles: Vec<&str> = snippets.iter().map(Snippet::title).collect();
+    assert_eq!(vec!["snippet3 with space", "snippet2", "snippet1"], titles);
+}
+
+#[test]
+fn take_snippet() {
+    let mut parser = SnippetParser::from_snippets(vec![
+        Snippet::new("first".to_string(), "1".to_string()),
+        Snippet::new("second".to_string(), "2".to_string())
+    ]);
+    assert_eq!(Snippet::new("first".to_string(), "1".to_string()), parser.take_snippet("first").unwrap());
+    assert!(matches!(parser.take_snippet("first"), Err(SnippetError::NotFound(title)) if title == "first"));
+    assert_eq!(None, parser.remove_snippet("missing"));
+    assert_eq!(vec![Snippet::new("second".to_string(), "2".to_string())], parser.get_snippets().unwrap());
+}
+
+#[test]
+fn checksums() {
+    let snippet = Snippet::new("title".to_string(), "The quick brown fox jumps over the lazy dog".to_string());
+    assert_eq!("-- title {crc32:414fa339} --\nThe quick brown fox jumps over the lazy dog\n-- end --", snippet.to_string_with_checksum());
+    
+    let path = temp_file("checksums", "checksums.snip", b"\
+-- valid {crc32:414fa339} --
+The quick brown fox jumps over the lazy dog
+-- end --
+-- corrupted {crc32:414fa339} --
+The quick brown fox jumps over the lazy cat
+-- end --
+");
+    let path = path.to_str().unwrap();
+    let mut parser = SnippetParser::read(path).unwrap().verify_checksums(true);
+    let err = parser.iter().unwrap().nth(1).unwrap().unwrap_err();
+    assert!(matches!(err, SnippetError::ChecksumMismatch(title) if title == "corrupted"));
+    assert_eq!(Some(Snippet::new("valid".to_string(), snippet.get_string().to_string())), parser.next());
+    
+    // Without verifying, checksums are only removed from the titles
+    let titles: Vec<String> = SnippetParser::read(path).unwrap().map(|snip| snip.title().to_string()).collect();
+    assert_eq!(vec!["valid", "corrupted"], titles);
+    
+    // Written checksums are verified when reading back
+    let written = SnippetParser::read(path).unwrap().write_checksums(true).to_string_with_eol("\n").unwrap();
+    assert!(written.contains("-- corrupted {crc32:"));
+    let path = temp_file("checksums", "written.snip", written.as_bytes());
+    assert_eq!(2, SnippetParser::read(path.to_str().unwrap()).unwrap().verify_checksums(true).get_snippets().unwrap().len());
+    
+    // A preserved header gets the checksum of the changed string
+    let path = temp_file("checksums", "preserved.snip", b"--  valid {crc32:414fa339}  --\nThe quick brown fox jumps over the lazy dog\n-- end --\n");
+    let path = path.to_str().unwrap();
+    let mut snippet = SnippetParser::read(path).unwrap().preserve_headers(true).verify_checksums(true).next().unwrap();
+    snippet.body_mut().push_str(" again");
+    SnippetParser::from_snippets(vec![snippet.clone()]).save(path).unwrap();
+    let read_back = SnippetParser::read(path).unwrap().preserve_headers(true).verify_checksums(true).get_snippets().unwrap();
+    assert_eq!(vec![snippet], read_back);
+    assert!(read_back[0].header().unwrap().starts_with("--  valid {crc32:"));
+    
+    // A mismatch does not stop reading the snippets after it
+    let path = temp_file("checksums", "mismatch_first.snip", b"\
+-- corrupted {crc32:414fa339} --
+The quick brown fox jumps over the lazy cat
+-- end --
+-- valid {crc32:414fa339} --
+The quick brown fox jumps over the lazy dog
+-- end --
+-- unchecked --
+body
+-- end --
+");
+    let path = path.to_str().unwrap();
+    let valid = Snippet::new("valid".to_string(), "The quick brown fox jumps over the lazy dog".to_string());
+    let unchecked = Snippet::new("unchecked".to_string(), "body".to_string());
+    let parser = SnippetParser::read(path).unwrap().verify_checksums(true);
+    assert_eq!(vec![valid.clone(), unchecked.clone()], parser.collect::<Vec<Snippet>>());
+    
+    let parser = SnippetParser::read(path).unwrap().verify_checksums(true);
+    let results: Vec<_> = parser.iter_with_index().unwrap().collect();
+    assert_eq!(3, results.len());
+    assert!(results[0].is_err());
+    assert_eq!((0, valid), *results[1].as_ref().unwrap());
+    assert_eq!((1, unchecked), *results[2].as_ref().unwrap());
+    assert!(parser.get_snippets().is_err());
+}
+
+#[test]
+fn chunks() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let chunks = parser.chunks(2).unwrap();
+    assert_eq!(vec![2, 1], chunks.iter().map(Vec::len).collect::<Vec<usize>>());
+    assert_eq!(parser.get_snippets().unwrap(), chunks.concat());
+}
+
+#[test]
+fn append_snippet() {
+    let mut first = Snippet::new("first".to_string(), "Are we human?".to_string());
+    let second = Snippet::new("second".to_string(), "Or are we dancer?".to_string());
+    first.append_snippet(&second, "\n");
+    assert_eq!(Snippet::new("first".to_string(), "Are we human?\nOr are we dancer?".to_string()), first);
+}
+
+#[test]
+fn raw_lines() {
+    let lines: Vec<String> = SnippetParser::raw_lines("./tests/snippets/snippet_test.snip").unwrap()
+        .map(|line| line.unwrap())
+        .collect();
+    assert_eq!(21, lines.len());
+    assert_eq!("-- snippet1 --", lines[0]);
+    assert_eq!("-- end --", lines[3]);
+    assert_eq!("", lines[4]);
+    assert_eq!("This is a comment", lines[10]);
+    assert_eq!("-- end --", lines[20]);
+    
+    let path = temp_file("raw_lines", "crlf.snip", b"-- title --\r\nbody\r\n-- end --");
+    let lines: Vec<String> = SnippetParser::raw_lines(path.to_str().unwrap()).unwrap().map(|line| line.unwrap()).collect();
+    assert_eq!(vec!["-- title --", "body", "-- end --"], lines);
+}
+
+#[test]
+fn trim_body_trailing_newline() {
+    let path = "./tests/snippets/snippet_test.snip";
+    let snippets = SnippetParser::read(path).unwrap().get_snippets().unwrap();
+    assert_eq!(vec![false, false, true], snippets.iter().map(|snip| snip.get_string().ends_with('\n')).collect::<Vec<bool>>());
+    
+    let snippets = SnippetParser::read(path).unwrap().trim_body_trailing_newline(true).get_snippets().unwrap();
+    assert!(snippets.iter().all(|snip| !snip.get_string().ends_with('\n')));
+    assert!(snippets[2].get_string().ends_with("Never gonna tell a lie and hurt you"));
+}
+
+#[test]
+fn insert_snippet() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test2.snip").unwrap();
+    parser.add_snippet(Snippet::new("second".to_string(), "2".to_string()));
+    parser.insert_snippet(0, Snippet::new("first".to_string(), "1".to_string()));
+    parser.insert_snippet(2, Snippet::new("third".to_string(), "3".to_string()));
+    
+    let mut expected = SnippetParser::read("./tests/snippets/snippet_test2.snip").unwrap().get_snippets().unwrap();
+    expected.extend([
+        Snippet::new("first".to_string(), "1".to_string()),
+        Snippet::new("second".to_string(), "2".to_string()),
+        Snippet::new("third".to_string(), "3".to_string())
+    ]);
+    assert_eq!(expected, parser.get_snippets().unwrap());
+}
+
+#[test]
+fn preview() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let snippet = parser.get_snippet("snippet3 with space").unwrap().unwrap();
+    assert_eq!("Never gonna give you up\nNever gonna let you down\n…", snippet.preview(2));
+    
+    let snippet = parser.get_snippet("snippet1").unwrap().unwrap();
+    assert_eq!("Are we human?\nOr are we dancer?", snippet.preview(2));
+}
+
+#[test]
+fn iter_titles_and_bodies() {
+    let parts = vec![("first".to_string(), "1".to_string()), ("second".to_string(), "2".to_string())];
+    let pointers: Vec<(*const u8, *const u8)> = parts.iter().map(|(title, body)| (title.as_ptr(), body.as_ptr())).collect();
+    let parser = SnippetParser::from_snippets(parts.into_iter().map(|(title, body)| Snippet::new(title, body)).collect());
+    
+    let titles_and_bodies: Vec<(&str, &str)> = parser.iter_titles_and_bodies().collect();
+    assert_eq!(vec![("first", "1"), ("second", "2")], titles_and_bodies);
+    for ((title, body), (title_ptr, body_ptr)) in titles_and_bodies.into_iter().zip(pointers) {
+        assert!(std::ptr::eq(title_ptr, title.as_ptr()));
+        assert!(std::ptr::eq(body_ptr, body.as_ptr()));
+    }
+    
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    assert_eq!(0, parser.iter_titles_and_bodies().count());
+}
+
+#[test]
+fn snippet_error_variants() {
+    // Io
+    let path = temp_file("snippet_error_variants", "removed.snip", b"-- title --\nbody\n-- end --\n");
+    let parser = SnippetParser::read(path.to_str().unwrap()).unwrap();
+    fs::remove_file(&path).unwrap();
+    let err = parser.require_snippet("title").unwrap_err();
+    assert!(matches!(err, SnippetError::Io(ref err) if err.kind() == std::io::ErrorKind::NotFound));
+    assert!(std::error::Error::source(&err).is_some());
+    
+    // Parse
+    let path = temp_file("snippet_error_variants", "invalid.snip", b"-- title --\nvalid\ninvalid \xFF\n-- end --\n");
+    let err = SnippetParser::read(path.to_str().unwrap()).unwrap().get_snippets().unwrap_err();
+    match err {
+        SnippetError::Parse { line, reason } => {
+            assert_eq!(3, line);
+            assert_eq!("line is not valid UTF-8", reason);
+        },
+        other => panic!("unexpected error: {:?}", other)
+    }
+    
+    // NotFound
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let err = parser.require_snippet("missing").unwrap_err();
+    assert!(matches!(err, SnippetError::NotFound(ref title) if title == "missing"));
+    assert!(std::error::Error::source(&err).is_none());
+    assert_eq!("no snippet with title \"missing\"", err.to_string());
+    
+    // TitleCollision
+    let parser = SnippetParser::from_snippets(vec![
+        Snippet::new("dup".to_string(), "1".to_string()),
+        Snippet::new("dup".to_string(), "2".to_string())
+    ]).strict(true);
+    let err = parser.write_to(&mut Vec::new()).unwrap_err();
+    assert!(matches!(err, SnippetError::TitleCollision(title) if title == "dup"));
+}
+
+#[test]
+fn append_from() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    parser.append_from("./tests/snippets/snippet_test2.snip").unwrap();
+    let mut expected = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap().get_snippets().unwrap();
+    expected.extend(SnippetParser::read("./tests/snippets/snippet_test2.snip").unwrap().get_snippets().unwrap());
+    assert_eq!(expected, parser.get_snippets().unwrap());
+    
+    assert!(parser.append_from("./tests/snippets/missing.snip").is_err());
+    assert_eq!(5, parser.get_snippets().unwrap().len());
+}
+
+#[test]
+fn body_contains() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let snippet = parser.get_snippet("snippet3 with space").unwrap().unwrap();
+    assert!(snippet.body_contains("gonna", false));
+    assert!(snippet.body_contains("gonna", true));
+    assert!(snippet.body_contains("gon", false));
+    assert!(!snippet.body_contains("gon", true));
+    assert!(snippet.body_contains("desert you", true));
+    assert!(!snippet.body_contains("dancer", false));
+}
+
+#[test]
+fn write_filtered() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let mut written = Vec::new();
+    parser.write_filtered(&mut written, |snip| snip.title().len() > 8).unwrap();
+    
+    let expected = "\
+-- snippet3 with space --
+Never gonna give you up
+Never gonna let you down
+Never gonna run around and desert you
+
+Never gonna make you cry
+Never gonna say goodbye
+Never gonna tell a lie and hurt you
+
+-- end --
+";
+    assert_eq!(expected, String::from_utf8(written).unwrap());
+}
+
+#[test]
+fn file_metadata() {
+    let path = temp_file("file_metadata", "front_matter.snip", b"\
+---
+author: Jomy10
+version: 1.0
+---
+-- snippet1 --
+Are we human?
+-- end --
+");
+    let parser = SnippetParser::read(path.to_str().unwrap()).unwrap().strict(true);
+    let metadata = parser.file_metadata().unwrap();
+    assert_eq!(2, metadata.len());
+    assert_eq!(Some(&"Jomy10".to_string()), metadata.get("author"));
+    assert_eq!(Some(&"1.0".to_string()), metadata.get("version"));
+    assert_eq!(vec![Snippet::new("snippet1".to_string(), "Are we human?".to_string())], parser.get_snippets().unwrap());
+    
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    assert!(parser.file_metadata().unwrap().is_empty());
+    assert_eq!(3, parser.get_snippets().unwrap().len());
+}
+
+#[test]
+fn unterminated_front_matter() {
+    let contents = "---\nauthor: Jomy10\n-- title --\nbody\n-- end --\n";
+    let path = temp_file("unterminated_front_matter", "unterminated.snip", contents.as_bytes());
+    let parser = SnippetParser::read(path.to_str().unwrap()).unwrap();
+    assert!(matches!(parser.get_snippets().unwrap_err(), SnippetError::Parse { line: 1, .. }));
+    assert!(matches!(parser.file_metadata().unwrap_err(), SnippetError::Parse { line: 1, .. }));
+    assert_eq!(vec![LintIssue::UnterminatedFrontMatter], parser.lint().unwrap());
+    
+    // Without an err to return, the `---` line is read as ordinary text, which is a header
+    assert_eq!(vec![Snippet::new("-".to_string(), "author: Jomy10\n-- title --\nbody".to_string())], parse_str(contents));
+}
+
+#[test]
+fn front_matter_round_trip() {
+    let contents = "---\nauthor: me\n---\n-- title --\nbody\n-- end --\n";
+    let path = temp_file("front_matter_round_trip", "front_matter.snip", contents.as_bytes());
+    let path = path.to_str().unwrap();
+    
+    SnippetParser::read(path).unwrap().rewrite_file().unwrap();
+    assert_eq!(contents, fs::read_to_string(path).unwrap());
+    
+    SnippetParser::format_file(path).unwrap();
+    assert_eq!(contents, fs::read_to_string(path).unwrap());
+    
+    let copy = temp_file("front_matter_round_trip_copy", "copy.snip", b"");
+    SnippetParser::read(path).unwrap().save(copy.to_str().unwrap()).unwrap();
+    assert_eq!(contents, fs::read_to_string(&copy).unwrap());
+}
+
+#[test]
+fn eq_unordered() {
+    let file_parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let mut reversed = file_parser.get_snippets_reversed().unwrap();
+    let parser = SnippetParser::from_snippets(reversed.clone());
+    assert!(file_parser.eq_unordered(&parser).unwrap());
+    
+    reversed.push(reversed[0].clone());
+    let parser = SnippetParser::from_snippets(reversed);
+    assert!(!file_parser.eq_unordered(&parser).unwrap());
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn titles_matching() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    assert_eq!(vec!["snippet1", "snippet2"], parser.titles_matching(r"^snippet\d$").unwrap());
+    assert_eq!(vec!["snippet3 with space"], parser.titles_matching("space").unwrap());
+    assert!(matches!(parser.titles_matching("(unclosed"), Err(SnippetError::Regex(_))));
+}
+
+#[test]
+fn plain() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let without_newline = parser.get_snippet("snippet1").unwrap().unwrap();
+    assert_eq!("Are we human?\nOr are we dancer?", without_newline.plain());
+    assert_eq!("Are we human?\nOr are we dancer?\n", without_newline.plain_with_newline());
+    
+    let with_newline = parser.get_snippet("snippet3 with space").unwrap().unwrap();
+    assert!(with_newline.plain().ends_with("hurt you\n"));
+    assert!(with_newline.plain_with_newline().ends_with("hurt you\n"));
+    assert!(!with_newline.plain_with_newline().ends_with("\n\n"));
+    
+    let multiple_newlines = Snippet::new("title".to_string(), "body\n\n\n".to_string());
+    assert_eq!("body\n", multiple_newlines.plain_with_newline());
+}
+
+#[test]
+fn into_materialized() {
+    let mut snippets = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap().into_materialized().unwrap();
+    assert_eq!("snippet1", snippets.next().unwrap().title());
+    assert_eq!("snippet3 with space", snippets.next_back().unwrap().title());
+    assert_eq!("snippet2", snippets.next().unwrap().title());
+    assert_eq!(None, snippets.next_back());
+    assert_eq!(None, snippets.next());
+}
+
+#[test]
+fn find_by_body() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let snippet = parser.find_by_body(|body| body.contains("church")).unwrap().unwrap();
+    assert_eq!("snippet2", snippet.title());
+    assert_eq!(None, parser.find_by_body(|body| body.contains("cathedral")).unwrap());
+}
+
+#[test]
+fn auto_dedent() {
+    let path = temp_file("auto_dedent", "indented.snip", b"\
+-- indented --
+    fn main() {
+        println!(\"Hello\");
+
+    }
+-- end --
+-- mixed --
+\tfirst
+  second
+-- end --
+");
+    let snippets = SnippetParser::read(path.to_str().unwrap()).unwrap().auto_dedent(true).get_snippets().unwrap();
+    assert_eq!(vec![
+        Snippet::new("indented".to_string(), "fn main() {\n    println!(\"Hello\");\n\n}".to_string()),
+        Snippet::new("mixed".to_string(), "\tfirst\n  second".to_string())
+    ], snippets);
+}
+
+#[test]
+fn into_parts() {
+    let snippet = Snippet::new("title".to_string(), "body".to_string());
+    let (title, body) = snippet.into_parts();
+    assert_eq!("title", title);
+    assert_eq!("body", body);
+}
+
+#[test]
+fn count_by() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    assert_eq!(1, parser.count_by(|snip| snip.get_string().lines().count() > 2).unwrap());
+    assert_eq!(3, parser.count_by(|_| true).unwrap());
+}
+
+#[test]
+fn collapse_blank_lines() {
+    let mut snippet = Snippet::new("title".to_string(), "first\n\n\n\nsecond\n\nthird".to_string());
+    snippet.collapse_blank_lines(1);
+    assert_eq!("first\n\nsecond\n\nthird", snippet.get_string());
+    
+    snippet.collapse_blank_lines(0);
+    assert_eq!("first\nsecond\nthird", snippet.get_string());
+    
+    let mut snippet = Snippet::new("title".to_string(), "\n\nfirst".to_string());
+    snippet.collapse_blank_lines(0);
+    assert_eq!("first", snippet.get_string());
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_round_trip() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let toml = parser.to_toml().unwrap();
+    assert!(toml.contains("[[snippets]]"));
+    assert!(toml.contains("title = \"snippet3 with space\""));
+    
+    let from_toml = SnippetParser::from_toml(&toml).unwrap();
+    assert_eq!(parser.get_snippets().unwrap(), from_toml.get_snippets().unwrap());
+    
+    assert!(matches!(SnippetParser::from_toml("[[snippets]]\ntitle = \"no body\""), Err(SnippetError::Toml(_))));
+    assert!(matches!(SnippetParser::from_toml("not toml"), Err(SnippetError::Toml(_))));
+}
+
+#[test]
+fn get_or_insert_with() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let existing = parser.get_or_insert_with("snippet1", || panic!("snippet1 exists")).unwrap();
+    assert_eq!("Are we human?\nOr are we dancer?", existing.get_string());
+    
+    let new = Snippet::new("snippet4".to_string(), "new".to_string());
+    assert_eq!(new, parser.get_or_insert_with("snippet4", || new.clone()).unwrap());
+    assert_eq!(new, parser.get_or_insert_with("snippet4", || panic!("snippet4 was inserted")).unwrap());
+    assert_eq!(4, parser.get_snippets().unwrap().len());
+}
+
+#[test]
+#[should_panic(expected = "instead of \"snippet5\"")]
+fn get_or_insert_with_other_title() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let _ = parser.get_or_insert_with("snippet5", || Snippet::new("snippet4".to_string(), "new".to_string()));
+}
+
+#[cfg(feature = "rand")]
+#[test]
+fn shuffle_with_seed() {
+    use rand::SeedableRng;
+    
+    let shuffled = |seed: u64| {
+        let mut parser = SnippetParser::read_many(&["./tests/snippets/snippet_test.snip", "./tests/snippets/snippet_test2.snip"]).unwrap();
+        parser.shuffle(&mut rand::rngs::StdRng::seed_from_u64(seed)).unwrap();
+        parser.get_snippets().unwrap()
+    };
+    let first = shuffled(42);
+    assert_eq!(first, shuffled(42));
+    
+    let original = SnippetParser::read_many(&["./tests/snippets/snippet_test.snip", "./tests/snippets/snippet_test2.snip"]).unwrap();
+    let original = original.get_snippets().unwrap();
+    let mut titles: Vec<&str> = first.iter().map(Snippet::title).collect();
+    let mut original_titles: Vec<&str> = original.iter().map(Snippet::title).collect();
+    titles.sort();
+    original_titles.sort();
+    assert_eq!(original_titles, titles);
+}
+
+#[test]
+fn with_line_numbers() {
+    let snippet = Snippet::new("listing".to_string(), "fn main() {\n    println!();\n}".to_string());
+    assert_eq!("  1 fn main() {\n  2     println!();\n  3 }", snippet.with_line_numbers(1, 3));
+    assert_eq!(" 9 fn main() {\n10     println!();\n11 }", snippet.with_line_numbers(9, 2));
+}
+
+#[test]
+fn rename_all() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    parser.rename_all(|title| format!("rust/{}", title)).unwrap();
+    let snippets = parser.get_snippets().unwrap();
+    assert_eq!(
+        vec![
+            Snippet::new("rust/snippet1".to_string(), "Are we human?\nOr are we dancer?".to_string()),
+            Snippet::new("rust/snippet2".to_string(), snippets[1].get_string().to_string()),
+            Snippet::new("rust/snippet3 with space".to_string(), snippets[2].get_string().to_string())
+        ],
+        snippets
+    );
+    
+    let err = parser.rename_all(|_| "same".to_string()).unwrap_err();
+    assert!(matches!(err, SnippetError::TitleCollision(title) if title == "same"));
+    assert_eq!(snippets, parser.get_snippets().unwrap());
+}
+
+#[test]
+fn read_lossy() {
+    let path = temp_file("read_lossy", "invalid.snip", b"-- latin1 --\ncaf\xE9 au lait\n-- end --\n-- after --\nstill read\n-- end --\n");
+    let snippets = SnippetParser::read_lossy(path.to_str().unwrap()).unwrap().get_snippets().unwrap();
+    assert_eq!(vec![
+        Snippet::new("latin1".to_string(), "caf\u{FFFD} au lait".to_string()),
+        Snippet::new("after".to_string(), "still read".to_string())
+    ], snippets);
+}
+
+#[test]
+fn body_eq_ignoring_whitespace() {
+    let indented = Snippet::new("indented".to_string(), "fn main() {\n    let  x = 1;\n}".to_string());
+    let flat = Snippet::new("flat".to_string(), "fn main() {\nlet x = 1;  \n}".to_string());
+    assert!(indented.body_eq_ignoring_whitespace(&flat));
+    
+    let joined = Snippet::new("joined".to_string(), "fn main() { let x = 1; }".to_string());
+    assert!(!indented.body_eq_ignoring_whitespace(&joined));
+}
+
+#[test]
+fn max_and_min_snippet_by_key() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let snippets = parser.get_snippets().unwrap();
+    let longest = parser.max_snippet_by_key(|snippet| snippet.get_string().lines().count()).unwrap();
+    assert_eq!(Some(snippets[2].clone()), longest);
+    
+    // snippet1 and snippet2 both have two lines, so the first one is returned
+    let shortest = parser.min_snippet_by_key(|snippet| snippet.get_string().lines().count()).unwrap();
+    assert_eq!(Some(snippets[0].clone()), shortest);
+    
+    assert_eq!(None, SnippetParser::new().max_snippet_by_key(|snippet| snippet.byte_len()).unwrap());
+}
+
+#[test]
+fn replace_snippet() {
+    let mut parser = SnippetParser::from_snippets(vec![
+        Snippet::new("first".to_string(), "1".to_string()),
+        Snippet::new("second".to_string(), "2".to_string()),
+        Snippet::new("third".to_string(), "3".to_string())
+    ]);
+    let new = Snippet::new("replaced".to_string(), "two".to_string());
+    assert!(parser.replace_snippet("second", new.clone()));
+    assert!(!parser.replace_snippet("second", new.clone()));
+    assert_eq!(vec![
+        Snippet::new("first".to_string(), "1".to_string()),
+        new,
+        Snippet::new("third".to_string(), "3".to_string())
+    ], parser.get_snippets().unwrap());
+}
+
+#[cfg(feature = "csv")]
+#[test]
+fn csv_round_trip() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let mut csv = Vec::new();
+    parser.to_csv(&mut csv).unwrap();
+    let csv = String::from_utf8(csv).unwrap();
+    assert!(csv.starts_with("title,body\nsnippet1,\"Are we human?\nOr are we dancer?\"\n"));
+    
+    let from_csv = SnippetParser::from_csv(csv.as_bytes()).unwrap();
+    assert_eq!(parser.get_snippets().unwrap(), from_csv.get_snippets().unwrap());
+    
+    assert!(matches!(SnippetParser::from_csv("name,body\na,b\n".as_bytes()), Err(SnippetError::Csv(_))));
+}
+
+#[test]
+fn apply_edits() {
+    let mut parser = SnippetParser::from_snippets(vec![
+        Snippet::new("first".to_string(), "1".to_string()),
+        Snippet::new("second".to_string(), "2".to_string())
+    ]);
+    parser.apply_edits(vec![
+        Edit::Add(Snippet::new("third".to_string(), "3".to_string())),
+        Edit::Remove("first".to_string()),
+        Edit::Update { title: "second".to_string(), body: "two".to_string() },
+        Edit::Rename { old: "third".to_string(), new: "last".to_string() }
+    ]).unwrap();
+    let edited = vec![
+        Snippet::new("second".to_string(), "two".to_string()),
+        Snippet::new("last".to_string(), "3".to_string())
+    ];
+    assert_eq!(edited, parser.get_snippets().unwrap());
+    
+    let err = parser.apply_edits(vec![
+        Edit::Remove("second".to_string()),
+        Edit::Update { title: "last".to_string(), body: "changed".to_string() },
+        Edit::Rename { old: "missing".to_string(), new: "found".to_string() }
+    ]).unwrap_err();
+    assert!(matches!(err, SnippetError::NotFound(title) if title == "missing"));
+    assert_eq!(edited, parser.get_snippets().unwrap());
+    
+    let err = parser.apply_edits(vec![Edit::Rename { old: "last".to_string(), new: "second".to_string() }]).unwrap_err();
+    assert!(matches!(err, SnippetError::TitleCollision(title) if title == "second"));
+}
+
+#[test]
+fn split_at_line() {
+    let snippet = Snippet::new("long".to_string(), "one\ntwo\nthree\nfour".to_string());
+    let (first, second) = snippet.split_at_line(2);
+    assert_eq!(Snippet::new("long (1)".to_string(), "one\ntwo".to_string()), first);
+    assert_eq!(Snippet::new("long (2)".to_string(), "three\nfour".to_string()), second);
+    
+    let (first, second) = snippet.split_at_line(10);
+    assert_eq!(snippet.get_string(), first.get_string());
+    assert_eq!("", second.get_string());
+}
+
+#[test]
+fn lint() {
+    let path = temp_file("lint", "lint.snip", b"-- first --\nbody\n-- end --\n-- end --\n-- empty --\n-- end --\n-- first --\nagain\n-- end --\n");
+    let issues = SnippetParser::read(path.to_str().unwrap()).unwrap().lint().unwrap();
+    assert_eq!(vec![
+        LintIssue::OrphanEndMarker { line: 4 },
+        LintIssue::EmptySnippet { line: 5, title: "empty".to_string() },
+        LintIssue::DuplicateTitle { line: 7, title: "first".to_string() }
+    ], issues);
+    
+    assert!(SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap().lint().unwrap().is_empty());
+}
+
+#[test]
+fn merge_three_way() {
+    let snippet = |title: &str, s: &str| Snippet::new(title.to_string(), s.to_string());
+    let base = SnippetParser::from_snippets(vec![snippet("same", "base"), snippet("conflict", "base"), snippet("ours only", "base")]);
+    let ours = SnippetParser::from_snippets(vec![snippet("same", "base"), snippet("conflict", "ours"), snippet("ours only", "changed")]);
+    let theirs = SnippetParser::from_snippets(vec![snippet("same", "base"), snippet("conflict", "theirs"), snippet("ours only", "base"), snippet("new", "added")]);
+    
+    let merged = SnippetParser::merge_three_way(&base, &ours, &theirs).unwrap();
+    assert_eq!(vec![
+        snippet("same", "base"),
+        snippet("conflict", "<<<<<<< ours\nours\n=======\ntheirs\n>>>>>>> theirs"),
+        snippet("ours only", "changed"),
+        snippet("new", "added")
+    ], merged.get_snippets().unwrap());
+}
+
+#[test]
+fn write_title_with_newline() {
+    let parser = SnippetParser::from_snippets(vec![Snippet::new("two\nlines".to_string(), "body".to_string())]);
+    let mut written = Vec::new();
+    parser.write_to(&mut written).unwrap();
+    let written = String::from_utf8(written).unwrap();
+    assert_eq!("-- two lines --\nbody\n-- end --\n", written);
+    assert_eq!(vec![Snippet::new("two lines".to_string(), "body".to_string())], parse_str(&written));
+    
+    let err = parser.strict(true).write_to(&mut Vec::new()).unwrap_err();
+    assert!(matches!(err, SnippetError::InvalidTitle(title) if title == "two\nlines"));
+    
+    // `--` would be removed when reading back, so it is rejected outside of strict mode too
+    let parser = SnippetParser::from_snippets(vec![Snippet::new("a -- b".to_string(), "body".to_string())]);
+    let err = parser.write_to(&mut Vec::new()).unwrap_err();
+    assert!(matches!(err, SnippetError::InvalidTitle(title) if title == "a -- b"));
+}
+
+#[test]
+fn get_snippets_between() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let titles = |snippets: Vec<Snippet>| snippets.iter().map(|snip| snip.title().to_string()).collect::<Vec<String>>();
+    assert_eq!(vec!["snippet1", "snippet2"], titles(parser.get_snippets_between("snippet1", "snippet2", true).unwrap()));
+    assert_eq!(vec!["snippet2"], titles(parser.get_snippets_between("snippet1", "snippet3 with space", false).unwrap()));
+    assert!(parser.get_snippets_between("snippet2", "snippet1", true).unwrap().is_empty());
+    assert!(parser.get_snippets_between("missing", "snippet2", true).unwrap().is_empty());
+    assert!(parser.get_snippets_between("snippet1", "missing", true).unwrap().is_empty());
+}
+
+#[test]
+fn expand_env() {
+    // Cargo sets `CARGO_MANIFEST_DIR` when running tests, so the environment doesn't have to be
+    // modified while other tests are running.
+    let dir = env!("CARGO_MANIFEST_DIR");
+    let snippet = Snippet::new("path".to_string(), "${CARGO_MANIFEST_DIR}/${SNIPPETS_RS_TEST_UNSET}".to_string());
+    assert_eq!(format!("{}/${{SNIPPETS_RS_TEST_UNSET}}", dir), snippet.expand_env());
+    assert!(matches!(snippet.try_expand_env(), Err(SnippetError::UnsetVariable(name)) if name == "SNIPPETS_RS_TEST_UNSET"));
+    
+    let with_default = Snippet::new("path".to_string(), "${CARGO_MANIFEST_DIR}/${SNIPPETS_RS_TEST_UNSET:index}".to_string());
+    assert_eq!(format!("{}/index", dir), with_default.try_expand_env().unwrap());
+}
+
+#[test]
+fn truncate() {
+    let mut parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let snippets = parser.get_snippets().unwrap();
+    parser.truncate(2).unwrap();
+    assert_eq!(snippets[..2].to_vec(), parser.get_snippets().unwrap());
+    assert_eq!(snippets[..2].to_vec(), parser.collect::<Vec<Snippet>>());
+}
+
+#[test]
+fn preview_save() {
+    let path = temp_file("preview_save", "saved.snip", b"");
+    let path = path.to_str().unwrap();
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let preview = parser.preview_save().unwrap();
+    parser.save(path).unwrap();
+    assert_eq!(fs::read_to_string(path).unwrap(), preview);
+}
+
+#[test]
+fn commented() {
+    let snippet = Snippet::new("code".to_string(), "let x = 1;\nlet y = 2;".to_string());
+    assert_eq!("// let x = 1;\n// let y = 2;", snippet.commented(CommentStyle::DoubleSlash));
+    assert_eq!("/*\nlet x = 1;\nlet y = 2;\n*/", snippet.commented(CommentStyle::Block));
+    
+    let with_blank = Snippet::new("script".to_string(), "echo 1\n\necho 2".to_string());
+    assert_eq!("# echo 1\n#\n# echo 2", with_blank.commented(CommentStyle::Hash));
+}
+
+#[test]
+fn load_or_default() {
+    let existing = SnippetParser::load_or_default("./tests/snippets/snippet_test.snip");
+    assert_eq!(3, existing.get_snippets().unwrap().len());
+    let existing = SnippetParser::try_load_or_default("./tests/snippets/snippet_test.snip").unwrap();
+    assert_eq!(3, existing.get_snippets().unwrap().len());
+    
+    let missing = SnippetParser::load_or_default("./tests/snippets/does_not_exist.snip");
+    assert!(missing.get_snippets().unwrap().is_empty());
+    let missing = SnippetParser::try_load_or_default("./tests/snippets/does_not_exist.snip").unwrap();
+    assert!(missing.get_snippets().unwrap().is_empty());
+}
+
+#[test]
+fn body_mut() {
+    let mut snippet = Snippet::new("todo".to_string(), "first".to_string());
+    snippet.body_mut().push_str("\n-- end --");
+    snippet.body_mut().insert_str(0, "zeroth\n");
+    assert_eq!("zeroth\nfirst\n-- end --", snippet.get_string());
+    assert_eq!(vec![snippet.clone()], parse_str(&format!("{}", snippet)));
+}
+
+#[test]
+fn iter_with_index() {
+    let parser = SnippetParser::read("./tests/snippets/snippet_test.snip").unwrap();
+    let indexed: Vec<(usize, Snippet)> = parser.iter_with_index().unwrap().map(Result::unwrap).collect();
+    let expected: Vec<(usize, Snippet)> = parser.get_snippets().unwrap().into_iter().enumerate().collect();
+    assert_eq!(expected, indexed);
+    assert_eq!(vec![0, 1, 2], indexed.iter().map(|(index, _)| *index).collect::<Vec<usize>>());
+    
+    let valid = Snippet::new("valid".to_string(), "body".to_string());
+    let contents = format!("-- invalid {{crc32:00000000}} --\nbody\n-- end --\n{}\n", valid.to_string_with_checksum());
+    let path = temp_file("iter_with_index", "checksums.snip", contents.as_bytes());
+    let mut parser = SnippetParser::read(path.to_str().unwrap()).unwrap().verify_checksums(true);
+    parser.add_snippet(Snippet::new("memory".to_string(), "in memory".to_string()));
+    let results: Vec<Result<(usize, Snippet), SnippetError>> = parser.iter_with_index().unwrap().collect();
+    assert_eq!(3, results.len());
+    assert!(matches!(results[0], Err(SnippetError::ChecksumMismatch(ref title)) if title == "invalid"));
+    assert_eq!(&(0, valid.clone()), results[1].as_ref().unwrap());
+    assert_eq!(&(1, Snippet::new("memory".to_string(), "in memory".to_string())), results[2].as_ref().unwrap());
+    
+    // A line that can't be read stops reading the file, but not the in-memory snippets
+    let path = temp_file("iter_with_index", "invalid.snip", b"-- invalid --\n\xFF\n-- end --\n-- valid --\nbody\n-- end --\n");
+    let mut parser = SnippetParser::read(path.to_str().unwrap()).unwrap();
+    parser.add_snippet(Snippet::new("memory".to_string(), "in memory".to_string()));
+    let results: Vec<Result<(usize, Snippet), SnippetError>> = parser.iter_with_index().unwrap().collect();
+    assert_eq!(2, results.len());
+    assert!(matches!(results[0], Err(SnippetError::Parse { line: 2, .. })));
+    assert_eq!(&(0, Snippet::new("memory".to_string(), "in memory".to_string())), results[1].as_ref().unwrap());
+}
@@ -0,0 +1,13 @@
+use std::process::Command;
+
+/// The core of the crate (`Snippet` and `parse_str`) should compile without the `std` feature.
+#[test]
+fn builds_without_std() {
+    let status = Command::new(env!("CARGO"))
+        .args(["check", "--lib", "--no-default-features", "--quiet", "--target-dir"])
+        .arg(concat!(env!("CARGO_MANIFEST_DIR"), "/target/no_std"))
+        .current_dir(env!("CARGO_MANIFEST_DIR"))
+        .status()
+        .unwrap();
+    assert!(status.success());
+}